@@ -0,0 +1,200 @@
+use std::{
+    fmt::Display,
+    panic::{self, AssertUnwindSafe},
+    str::FromStr,
+};
+
+use crate::rng::Rng;
+
+/// Bytes the parsers give a meaning to, mutations favour them over random
+/// ones to get past the first checks
+const SPECIAL: &[u8] = b"0123456789 \n\r\t-,[]$/.abcdxyzABCXYZ";
+
+/// Words of the inputs and numbers at the edges of the integer types
+const TOKENS: &[&str] = &[
+    "0",
+    "-1",
+    "255",
+    "4294967295",
+    "4294967296",
+    "18446744073709551615",
+    "18446744073709551616",
+    "340282366920938463463374607431768211456",
+    "move ",
+    " from ",
+    " to ",
+    "$ cd ",
+    "$ ls",
+    "dir ",
+    "..",
+    "é",
+];
+
+/// The most inputs kept around to mutate
+const MAX_CORPUS: usize = 256;
+
+/// An input the target panicked on, as small as it could be made
+#[derive(Debug)]
+pub struct Crash {
+    pub input: String,
+    pub message: String,
+}
+
+impl Display for Crash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Panicked with {:?} on input {:?}",
+            self.message, self.input
+        )
+    }
+}
+
+/// Runs the target on the input, returns the panic message if it panicked
+fn run(target: &impl Fn(&str), input: &[u8]) -> Option<String> {
+    let input = String::from_utf8_lossy(input);
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| target(&input))).err()?;
+    Some(match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map_or("Unknown panic".to_owned(), |message| (*message).to_owned()),
+    })
+}
+
+/// Changes the input a little: replaces, inserts, removes or duplicates bytes,
+/// or splices it with another input of the corpus
+fn mutate(rng: &mut Rng, input: &mut Vec<u8>, corpus: &[Vec<u8>]) {
+    let position = rng.between(0, input.len());
+    match rng.below(7) {
+        0 if position < input.len() => input[position] = *rng.pick(SPECIAL),
+        1 if position < input.len() => input[position] = rng.below(256) as u8,
+        2 => input.insert(position, *rng.pick(SPECIAL)),
+        3 => {
+            let end = rng.between(position, input.len().min(position + 8));
+            input.drain(position..end);
+        }
+        4 => {
+            let end = rng.between(position, input.len().min(position + 16));
+            let copy = input[position..end].to_vec();
+            let at = rng.between(0, input.len());
+            input.splice(at..at, copy);
+        }
+        5 => {
+            let token = rng.pick(TOKENS).bytes();
+            input.splice(position..position, token);
+        }
+        _ => {
+            let other = rng.pick(corpus);
+            let start = rng.between(0, other.len());
+            input.truncate(position);
+            input.extend_from_slice(&other[start..]);
+        }
+    }
+}
+
+/// Removes parts of a crashing input for as long as it still crashes
+fn minimize(target: &impl Fn(&str), mut input: Vec<u8>) -> Vec<u8> {
+    let mut chunk = input.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start + chunk <= input.len() {
+            let mut smaller = input.clone();
+            smaller.drain(start..start + chunk);
+            if run(target, &smaller).is_some() {
+                input = smaller;
+            } else {
+                start += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    input
+}
+
+/// Feeds the target the corpus, then `iterations` mutations of it. Inputs
+/// that don't make it panic are sometimes added to the corpus so mutations
+/// pile up.
+///
+/// The target gets strings as every parser does, invalid UTF-8 is replaced.
+pub fn fuzz(
+    corpus: &[&str],
+    seed: u64,
+    iterations: usize,
+    target: impl Fn(&str),
+) -> Result<(), Crash> {
+    let mut rng = Rng::new(seed);
+    let mut corpus = corpus
+        .iter()
+        .map(|input| input.as_bytes().to_vec())
+        .collect::<Vec<_>>();
+    if corpus.is_empty() {
+        corpus.push(Vec::default());
+    }
+    let crash = |input: Vec<u8>, message| {
+        let input = minimize(&target, input);
+        let message = run(&target, &input).unwrap_or(message);
+        Err(Crash {
+            input: String::from_utf8_lossy(&input).into_owned(),
+            message,
+        })
+    };
+    for input in &corpus {
+        if let Some(message) = run(&target, input) {
+            return crash(input.clone(), message);
+        }
+    }
+    for _ in 0..iterations {
+        let mut input = rng.pick(&corpus).clone();
+        for _ in 0..rng.between(1, 4) {
+            mutate(&mut rng, &mut input, &corpus);
+        }
+        if let Some(message) = run(&target, &input) {
+            return crash(input, message);
+        }
+        if rng.one_in(8) {
+            if corpus.len() < MAX_CORPUS {
+                corpus.push(input);
+            } else {
+                let replaced = rng.below(corpus.len());
+                corpus[replaced] = input;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Fuzzes the target from a test, panicking with the smallest crashing input
+/// found. `FUZZ_ITERATIONS` and `FUZZ_SEED` override the 2000 iterations and
+/// the seed for longer runs.
+pub fn check(corpus: &[&str], target: impl Fn(&str)) {
+    fn variable<T: FromStr>(name: &str) -> Option<T> {
+        std::env::var(name).ok()?.parse().ok()
+    }
+    let iterations = variable("FUZZ_ITERATIONS").unwrap_or(2000);
+    let seed = variable("FUZZ_SEED").unwrap_or(0);
+    if let Err(crash) = fuzz(corpus, seed, iterations, target) {
+        panic!("{crash}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzz;
+
+    #[test]
+    fn finds_and_minimizes_crashes() {
+        let parse = |input: &str| {
+            for line in input.lines() {
+                let (a, b) = line.split_once('-').unwrap_or(("0", "0"));
+                let _ = a.trim().parse::<u8>().unwrap() + b.trim().parse::<u8>().unwrap();
+            }
+        };
+        let crash = fuzz(&["1-2\n3-4\n"], 1, 1000, parse).unwrap_err();
+        assert!(crash.input.len() <= 3, "{crash}");
+        assert!(fuzz(&["1-2\n"], 1, 1000, |input| {
+            let _ = input.lines().count();
+        })
+        .is_ok());
+    }
+}
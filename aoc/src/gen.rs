@@ -0,0 +1,357 @@
+use std::{collections::HashSet, fmt::Write};
+
+use crate::rng::Rng;
+
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const ITEMS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The days with a generator
+pub const DAYS: std::ops::RangeInclusive<u32> = 1..=7;
+
+/// How large the input of a day is when no size is given, close to the real
+/// puzzle inputs
+pub fn default_size(day: u32) -> usize {
+    match day {
+        1 => 250,
+        2 => 2500,
+        3 => 100,
+        4 => 1000,
+        5 => 500,
+        6 => 4096,
+        _ => 300,
+    }
+}
+
+/// Generates a valid input for a day, `None` if the day has no generator.
+///
+/// `size` is the number of elves, rounds, groups of three rucksacks, section
+/// pairs, moves, letters or files depending on the day.
+pub fn generate(day: u32, seed: u64, size: usize) -> Option<String> {
+    let rng = &mut Rng::new(seed);
+    Some(match day {
+        1 => inventories(rng, size),
+        2 => strategy_guide(rng, size),
+        3 => rucksacks(rng, size),
+        4 => section_pairs(rng, size),
+        5 => rearrangement(rng, size),
+        6 => datastream(rng, size).0,
+        7 => terminal_session(rng, size),
+        _ => return None,
+    })
+}
+
+fn letters(rng: &mut Rng, alphabet: &[u8], count: usize) -> String {
+    (0..count).map(|_| *rng.pick(alphabet) as char).collect()
+}
+
+/// Elves carrying 1 to 15 snacks each, separated by blank lines
+pub fn inventories(rng: &mut Rng, size: usize) -> String {
+    let elves = (0..size)
+        .map(|_| {
+            let snacks = rng.between(1, 15);
+            (0..snacks)
+                .map(|_| format!("{}\n", rng.between(1000, 60000)))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    elves.join("\n")
+}
+
+pub fn strategy_guide(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            format!(
+                "{} {}\n",
+                rng.pick(&["A", "B", "C"]),
+                rng.pick(&["X", "Y", "Z"])
+            )
+        })
+        .collect()
+}
+
+/// Groups of three rucksacks. The two compartments of a rucksack share
+/// exactly one item type, and the three rucksacks of a group exactly one,
+/// their badge.
+pub fn rucksacks(rng: &mut Rng, size: usize) -> String {
+    let mut output = String::default();
+    for _ in 0..size {
+        let mut items = ITEMS.to_vec();
+        rng.shuffle(&mut items);
+        let badge = items[0];
+        // Each rucksack has its own 17 item types: one in both compartments,
+        // 8 only in the first one and 8 only in the second one
+        for pool in items[1..].chunks(17) {
+            let (shared, first, second) = (pool[0], &pool[1..9], &pool[9..]);
+            let half = rng.between(2, 16);
+            let mut compartments = [vec![shared], vec![shared]];
+            compartments[rng.below(2)].push(badge);
+            for (compartment, pool) in compartments.iter_mut().zip([first, second]) {
+                while compartment.len() < half {
+                    compartment.push(*rng.pick(pool));
+                }
+                rng.shuffle(compartment);
+                output.extend(compartment.iter().map(|item| *item as char));
+            }
+            output.push('\n');
+        }
+    }
+    output
+}
+
+pub fn section_pairs(rng: &mut Rng, size: usize) -> String {
+    let mut range = || {
+        let start = rng.between(1, 99);
+        format!("{start}-{}", rng.between(start, 99))
+    };
+    (0..size)
+        .map(|_| {
+            let first = range();
+            format!("{first},{}\n", range())
+        })
+        .collect()
+}
+
+/// A drawing of 3 to 9 stacks followed by moves that are valid with every
+/// crane, only the number of crates matters
+pub fn rearrangement(rng: &mut Rng, size: usize) -> String {
+    let count = rng.between(3, 9);
+    let mut stacks = (0..count)
+        .map(|_| {
+            let height = rng.between(0, 8);
+            letters(rng, UPPERCASE, height).into_bytes()
+        })
+        .collect::<Vec<_>>();
+    if stacks.iter().all(Vec::is_empty) {
+        stacks[0].push(b'A');
+    }
+
+    let mut output = String::default();
+    let height = stacks.iter().map(Vec::len).max().unwrap_or(0);
+    for row in (0..height).rev() {
+        let cells = stacks
+            .iter()
+            .map(|stack| {
+                stack
+                    .get(row)
+                    .map_or("   ".to_owned(), |label| format!("[{}]", *label as char))
+            })
+            .collect::<Vec<_>>();
+        let _ = writeln!(output, "{}", cells.join(" "));
+    }
+    let ids = (1..=count).map(|id| format!(" {id} ")).collect::<Vec<_>>();
+    let _ = writeln!(output, "{}\n", ids.join(" "));
+
+    for _ in 0..size {
+        let from = loop {
+            let from = rng.below(count);
+            if !stacks[from].is_empty() {
+                break from;
+            }
+        };
+        let to = (from + rng.between(1, count - 1)) % count;
+        let quantity = rng.between(1, stacks[from].len());
+        let remaining = stacks[from].len() - quantity;
+        let moved = stacks[from].split_off(remaining);
+        stacks[to].extend(moved);
+        let _ = writeln!(output, "move {quantity} from {} to {}", from + 1, to + 1);
+    }
+    output
+}
+
+/// A datastream of at least `size` letters, with where its start-of-packet
+/// and start-of-message markers end.
+///
+/// Before a marker of `n` letters only `n - 1` different letters are used, so
+/// nothing can be mistaken for it.
+pub fn datastream(rng: &mut Rng, size: usize) -> (String, [usize; 2]) {
+    let mut letters = LOWERCASE.to_vec();
+    rng.shuffle(&mut letters);
+    let mut stream = Vec::default();
+    let mut ends = [0; 2];
+    for (end, window, candidates) in [(0, 4, 13), (1, 14, 26)] {
+        let alphabet = &letters[..window - 1];
+        for _ in 0..rng.between(0, size / 4) {
+            stream.push(*rng.pick(alphabet));
+        }
+        // Repeating the second to last letter of the marker right before it
+        // puts a duplicate in every window overlapping the marker
+        let repeated = *rng.pick(alphabet);
+        let mut marker = letters[..candidates]
+            .iter()
+            .copied()
+            .filter(|letter| *letter != repeated)
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut marker);
+        marker.truncate(window - 1);
+        marker.insert(window - 2, repeated);
+        stream.extend(std::iter::repeat_n(repeated, window - 1));
+        stream.extend(marker);
+        ends[end] = stream.len();
+    }
+    while stream.len() < size {
+        stream.push(*rng.pick(LOWERCASE));
+    }
+    let mut stream = String::from_utf8(stream).unwrap();
+    stream.push('\n');
+    (stream, ends)
+}
+
+/// A session exploring a random filesystem with `size` files up to 6
+/// directories deep, every directory is listed once
+pub fn terminal_session(rng: &mut Rng, size: usize) -> String {
+    enum Entry {
+        Dir(usize),
+        File(u32),
+    }
+
+    #[derive(Default)]
+    struct Dir {
+        depth: usize,
+        names: HashSet<String>,
+        entries: Vec<(String, Entry)>,
+    }
+
+    fn name(rng: &mut Rng, names: &mut HashSet<String>, extension: bool) -> String {
+        loop {
+            let length = rng.between(1, 8);
+            let mut name = letters(rng, LOWERCASE, length);
+            if extension && rng.one_in(2) {
+                name.push('.');
+                name.push_str(&letters(rng, LOWERCASE, 3));
+            }
+            if names.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    fn list(dirs: &[Dir], id: usize, output: &mut String) {
+        output.push_str("$ ls\n");
+        for (name, entry) in &dirs[id].entries {
+            let _ = match entry {
+                Entry::Dir(_) => writeln!(output, "dir {name}"),
+                Entry::File(size) => writeln!(output, "{size} {name}"),
+            };
+        }
+        for (name, entry) in &dirs[id].entries {
+            if let Entry::Dir(child) = entry {
+                let _ = writeln!(output, "$ cd {name}");
+                list(dirs, *child, output);
+                output.push_str("$ cd ..\n");
+            }
+        }
+    }
+
+    let mut dirs = vec![Dir::default()];
+    for _ in 0..size {
+        let mut dir = rng.below(dirs.len());
+        if dirs[dir].depth < 6 && rng.one_in(4) {
+            let child = dirs.len();
+            let name = name(rng, &mut dirs[dir].names, false);
+            dirs[dir].entries.push((name, Entry::Dir(child)));
+            dirs.push(Dir {
+                depth: dirs[dir].depth + 1,
+                ..Dir::default()
+            });
+            dir = child;
+        }
+        let name = name(rng, &mut dirs[dir].names, true);
+        let file_size = rng.between(1000, 300000) as u32;
+        dirs[dir].entries.push((name, Entry::File(file_size)));
+    }
+    for dir in dirs.iter_mut() {
+        rng.shuffle(&mut dir.entries);
+    }
+
+    let mut output = "$ cd /\n".to_owned();
+    list(&dirs, 0, &mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{datastream, generate};
+    use crate::rng::Rng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn seeded() {
+        for day in 1..=7 {
+            let input = generate(day, 7, 20).unwrap();
+            assert_eq!(generate(day, 7, 20).unwrap(), input);
+            assert_ne!(generate(day, 8, 20).unwrap(), input);
+        }
+        assert_eq!(generate(8, 7, 20), None);
+    }
+
+    #[test]
+    fn rucksacks() {
+        let input = generate(3, 1, 50).unwrap();
+        let rucksacks = input
+            .lines()
+            .map(|line| line.bytes().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(rucksacks.len(), 150);
+        for rucksack in &rucksacks {
+            let (first, second) = rucksack.split_at(rucksack.len() / 2);
+            let first = first.iter().collect::<HashSet<_>>();
+            assert_eq!(
+                second
+                    .iter()
+                    .filter(|item| first.contains(item))
+                    .collect::<HashSet<_>>()
+                    .len(),
+                1
+            );
+        }
+        for group in rucksacks.chunks(3) {
+            let badges = group[0]
+                .iter()
+                .filter(|item| group[1].contains(item) && group[2].contains(item))
+                .collect::<HashSet<_>>();
+            assert_eq!(badges.len(), 1);
+        }
+    }
+
+    #[test]
+    fn planted_markers() {
+        let first_marker = |stream: &[u8], window: usize| {
+            stream
+                .windows(window)
+                .position(|w| w.iter().collect::<HashSet<_>>().len() == window)
+                .map(|start| start + window)
+        };
+        for seed in 0..200 {
+            let (stream, [packet, message]) = datastream(&mut Rng::new(seed), 100);
+            let stream = stream.trim_end().as_bytes();
+            assert!(stream.len() >= 100);
+            assert_eq!(first_marker(stream, 4), Some(packet));
+            assert_eq!(first_marker(stream, 14), Some(message));
+        }
+    }
+
+    #[test]
+    fn valid_moves() {
+        let input = generate(5, 3, 200).unwrap();
+        let (drawing, moves) = input.split_once("\n\n").unwrap();
+        let mut heights = vec![0; drawing.lines().last().unwrap().split_whitespace().count()];
+        for row in drawing.lines() {
+            for (index, cell) in row.as_bytes().chunks(4).enumerate() {
+                if cell[0] == b'[' {
+                    heights[index] += 1;
+                }
+            }
+        }
+        for line in moves.lines() {
+            let numbers = line
+                .split_whitespace()
+                .filter_map(|word| word.parse::<usize>().ok())
+                .collect::<Vec<_>>();
+            let (quantity, from, to) = (numbers[0], numbers[1] - 1, numbers[2] - 1);
+            assert!(from != to && 1 <= quantity && quantity <= heights[from]);
+            heights[from] -= quantity;
+            heights[to] += quantity;
+        }
+    }
+}
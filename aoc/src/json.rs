@@ -0,0 +1,389 @@
+use std::fmt::{Display, Write};
+
+use crate::Error;
+
+/// A parsed JSON document, object keys keep their order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The value of a key of an object
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number if it is a whole one that fits
+    pub fn as_u64(&self) -> Option<u64> {
+        let n = self.as_f64()?;
+        (n >= 0.0 && n.fract() == 0.0 && n <= u64::MAX as f64).then_some(n as u64)
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_owned())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Self {
+        Self::Number(n as f64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(n: u32) -> Self {
+        Self::Number(n.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Self::Number(n)
+    }
+}
+
+/// Quotes a string for JSON
+fn write_string(f: &mut impl Write, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if u32::from(c) < 0x20 => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Compact JSON, on a single line
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Number(n) if n.is_finite() => write!(f, "{n}"),
+            Self::Number(_) => f.write_str("null"),
+            Self::String(s) => write_string(f, s),
+            Self::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Self::Object(entries) => {
+                f.write_char('{')?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// Parses a whole JSON document
+pub fn parse(s: &str) -> Result<Value, Error> {
+    let mut parser = Parser {
+        bytes: s.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position < parser.bytes.len() {
+        return Err(parser.error("Trailing characters"));
+    }
+    Ok(value)
+}
+
+/// How deep arrays and objects can be nested, so a hostile file can't
+/// overflow the stack
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &'static str) -> Error {
+        Error::Json {
+            position: self.position,
+            reason,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Skips whitespace, then consumes `byte` if it is next
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.bytes.get(self.position) == Some(&byte);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8, reason: &'static str) -> Result<(), Error> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.error(reason))
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, Error> {
+        if self.bytes[self.position..].starts_with(keyword.as_bytes()) {
+            self.position += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error("Unknown keyword"))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("Nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.bytes.get(self.position) {
+            None => Err(self.error("Unexpected end")),
+            Some(b'n') => self.keyword("null", Value::Null),
+            Some(b't') => self.keyword("true", Value::Bool(true)),
+            Some(b'f') => self.keyword("false", Value::Bool(false)),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b'[') => {
+                self.position += 1;
+                let mut values = Vec::default();
+                if !self.eat(b']') {
+                    loop {
+                        values.push(self.value(depth + 1)?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        self.expect(b',', "Expected ',' or ']'")?;
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut entries = Vec::default();
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(b':', "Expected ':'")?;
+                        entries.push((key, self.value(depth + 1)?));
+                        if self.eat(b'}') {
+                            break;
+                        }
+                        self.expect(b',', "Expected ',' or '}'")?;
+                    }
+                }
+                Ok(Value::Object(entries))
+            }
+            Some(_) => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, Error> {
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_digit() || b"+-.eE".contains(b))
+        {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .ok()
+            .and_then(|number| number.parse::<f64>().ok())
+            .filter(|number| number.is_finite())
+            .map(Value::Number)
+            .ok_or(Error::Json {
+                position: start,
+                reason: "Invalid value",
+            })
+    }
+
+    /// Four hexadecimal digits of a `\u` escape
+    fn code_unit(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+        self.position += 4;
+        Ok(digits)
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        if self.bytes.get(self.position) != Some(&b'"') {
+            return Err(self.error("Expected a string"));
+        }
+        self.position += 1;
+        let mut bytes = Vec::default();
+        loop {
+            let Some(&byte) = self.bytes.get(self.position) else {
+                return Err(self.error("Unterminated string"));
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.bytes.get(self.position) else {
+                        return Err(self.error("Unterminated string"));
+                    };
+                    self.position += 1;
+                    let c = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.code_unit()?;
+                            // A surrogate pair is written as two escapes
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.bytes[self.position..].starts_with(b"\\u")
+                            {
+                                self.position += 2;
+                                let low = self.code_unit()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("Invalid unicode escape"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("Invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("Invalid escape")),
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte if byte < 0x20 => return Err(self.error("Control character in a string")),
+                byte => bytes.push(byte),
+            }
+        }
+        // The input is a str and escapes are pushed as UTF-8
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Value};
+    use crate::{fuzz, Error};
+
+    #[test]
+    fn round_trip() {
+        let text = r#" {"a": [1, 2.5, -3e2, true, false, null], "b\"\n": {"c": "éé😀"}, "d": []} "#;
+        let value = parse(text).unwrap();
+        assert_eq!(
+            value.get("a").unwrap().as_array().unwrap()[2].as_f64(),
+            Some(-300.0)
+        );
+        assert_eq!(
+            value.get("b\"\n").unwrap().get("c").unwrap().as_str(),
+            Some("éé😀")
+        );
+        assert_eq!(
+            value.to_string(),
+            r#"{"a":[1,2.5,-300,true,false,null],"b\"\n":{"c":"éé😀"},"d":[]}"#
+        );
+        assert_eq!(parse(&value.to_string()).unwrap(), value);
+        // Control characters without a short escape are written as \u escapes
+        assert_eq!(
+            Value::from("\u{1}\t\u{1f}").to_string(),
+            r#""\u0001\t\u001f""#
+        );
+        assert_eq!(Value::from(1700000000u64).as_u64(), Some(1700000000));
+        assert_eq!(Value::from(1.5).as_u64(), None);
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text| match parse(text) {
+            Err(Error::Json { position, reason }) => (position, reason),
+            result => panic!("Unexpected {result:?}"),
+        };
+        assert_eq!(error(""), (0, "Unexpected end"));
+        assert_eq!(error("[1 2]"), (3, "Expected ',' or ']'"));
+        assert_eq!(error("{\"a\" 1}"), (5, "Expected ':'"));
+        assert_eq!(error("\"abc"), (4, "Unterminated string"));
+        assert_eq!(error("nul"), (0, "Unknown keyword"));
+        assert_eq!(error("1 2"), (2, "Trailing characters"));
+        assert_eq!(error("-"), (0, "Invalid value"));
+        assert_eq!(error(&"[".repeat(1000)), (129, "Nested too deeply"));
+    }
+
+    #[test]
+    fn fuzz_parse() {
+        let corpus = [r#"{"a": [1, -2.5e3, true, null, "b\né"], "c": {}}"#];
+        fuzz::check(&corpus, |text| {
+            if let Ok(value) = parse(text) {
+                assert_eq!(parse(&value.to_string()).unwrap(), value);
+            }
+        });
+    }
+}
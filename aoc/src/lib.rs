@@ -0,0 +1,48 @@
+//! Shared helpers of the solutions: seeded random inputs for every day, used
+//! by the `aoc` command and by the property tests of the days, a small fuzzer
+//! for their parsers, and the submission and history of answers
+pub mod fuzz;
+pub mod gen;
+pub mod json;
+pub mod rng;
+pub mod run;
+pub mod store;
+pub mod submit;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The server couldn't be reached or didn't answer as expected
+    Http(String),
+    /// The byte offset of malformed JSON and what is wrong there
+    Json {
+        position: usize,
+        reason: &'static str,
+    },
+    /// A JSON document that doesn't have the expected fields
+    InvalidStore(String),
+    /// The answer is known to be wrong without asking the server
+    Rejected(String),
+    /// A solution didn't build or failed
+    Run(String),
+    IO(std::io::Error),
+}
+impl std::error::Error for Error {}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(e) => f.write_fmt(format_args!("HTTP error {e}")),
+            Self::Json { position, reason } => {
+                f.write_fmt(format_args!("Invalid JSON at byte {position}: {reason}"))
+            }
+            Self::InvalidStore(e) => f.write_fmt(format_args!("Invalid answer store {e}")),
+            Self::Rejected(e) => f.write_fmt(format_args!("Not submitted: {e}")),
+            Self::Run(e) => f.write_fmt(format_args!("Run failed: {e}")),
+            Self::IO(e) => f.write_fmt(format_args!("IO error {e}")),
+        }
+    }
+}
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::IO(e)
+    }
+}
@@ -0,0 +1,250 @@
+use aoc::{
+    gen, run,
+    store::{self, Run, Store},
+    submit::{self, Endpoint, Outcome},
+};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const USAGE: &str = "Usage: aoc gen <day> [--seed N] [--size N]
+       aoc run <day>
+       aoc history <day>
+       aoc submit <day> <part> [answer]";
+
+/// The directory holding the days
+const ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/..");
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidArgument(String),
+    UnknownDay(u32),
+    NoSolution(u32),
+    /// `AOC_SESSION` must hold the session cookie of the website to submit
+    MissingSession,
+    Aoc(aoc::Error),
+}
+impl std::error::Error for Error {}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidArgument(e) => f.write_fmt(format_args!("{e}\n{USAGE}")),
+            Self::UnknownDay(day) => f.write_fmt(format_args!("No generator for day {day}")),
+            Self::NoSolution(day) => {
+                f.write_fmt(format_args!("There is no solution for day {day}"))
+            }
+            Self::MissingSession => f.write_str(
+                "Set AOC_SESSION to the session cookie of the website to submit answers",
+            ),
+            Self::Aoc(e) => e.fmt(f),
+        }
+    }
+}
+impl From<aoc::Error> for Error {
+    fn from(e: aoc::Error) -> Self {
+        Self::Aoc(e)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidArgument(message.into())
+}
+
+/// Parses a number following a flag
+fn number<T: std::str::FromStr>(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<T, Error> {
+    args.next()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid(format!("{flag} expects a number")))
+}
+
+/// Parses `<day> [--seed N] [--size N]` and prints the generated input. Without
+/// a seed one is picked and shown so the input can be generated again.
+fn generate(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let day = number::<u32>(&mut args, "gen")?;
+    if !gen::DAYS.contains(&day) {
+        return Err(Error::UnknownDay(day));
+    }
+    let (mut seed, mut size) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = Some(number(&mut args, "--seed")?),
+            "--size" => size = Some(number(&mut args, "--size")?),
+            arg => return Err(invalid(format!("Unexpected argument {arg}"))),
+        }
+    }
+    let seed = seed.unwrap_or_else(|| {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        eprintln!("Seed: {seed}");
+        seed
+    });
+    let size = size.unwrap_or_else(|| gen::default_size(day));
+    let input = gen::generate(day, seed, size).ok_or(Error::UnknownDay(day))?;
+    print!("{input}");
+    Ok(())
+}
+
+/// The answers and submissions, in `answers.json` next to the days unless
+/// `AOC_STORE` says otherwise
+fn open_store() -> Result<Store, Error> {
+    let path = std::env::var_os("AOC_STORE")
+        .map_or_else(|| Path::new(ROOT).join("answers.json"), PathBuf::from);
+    Ok(Store::open(path)?)
+}
+
+/// Parses the day of a command that works on a solution, with its directory
+fn solution(
+    args: &mut impl Iterator<Item = String>,
+    command: &str,
+) -> Result<(u32, PathBuf), Error> {
+    let day = number::<u32>(args, command)?;
+    let directory = Path::new(ROOT).join(format!("day{day}"));
+    if !directory.join("Cargo.toml").is_file() {
+        return Err(Error::NoSolution(day));
+    }
+    Ok((day, directory))
+}
+
+fn no_more_arguments(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    match args.next() {
+        Some(arg) => Err(invalid(format!("Unexpected argument {arg}"))),
+        None => Ok(()),
+    }
+}
+
+/// Parses `<day>`, runs the solution and records the answers it prints, see
+/// [`run::answers`]. Answers that are known to be wrong are pointed out.
+fn run(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let (day, directory) = solution(&mut args, "run")?;
+    no_more_arguments(args)?;
+    let (output, runtime) = run::run_day(&directory, day)?;
+    print!("{output}");
+    eprintln!("Ran in {runtime:.2?}");
+
+    let answers = run::answers(day, &output)?;
+
+    let mut store = open_store()?;
+    let input_hash = run::input_hash(&directory)?;
+    let revision = run::revision(&directory);
+    for (part, answer) in answers {
+        if let Some(reason) = store.known_wrong(day, part, &answer) {
+            eprintln!("Warning: day {day} part {part}: {reason}");
+        }
+        store.record_run(Run {
+            day,
+            part,
+            answer,
+            input_hash: input_hash.clone(),
+            timestamp: store::now(),
+            revision: revision.clone(),
+            runtime,
+        });
+    }
+    Ok(store.save()?)
+}
+
+/// Parses `<day>` and prints how the answers and runtimes of the day evolved,
+/// with what the server said about them
+fn history(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let (day, _) = solution(&mut args, "history")?;
+    no_more_arguments(args)?;
+    let store = open_store()?;
+    if store.runs(day).next().is_none() {
+        println!("Day {day} was never run, see aoc run {day}");
+        return Ok(());
+    }
+    for part in 1..=2 {
+        let runs = store
+            .runs(day)
+            .filter(|run| run.part == part)
+            .collect::<Vec<_>>();
+        if runs.is_empty() {
+            continue;
+        }
+        match store.right_answer(day, part) {
+            Some(right) => println!("Part {part}, the answer is {right}"),
+            None => println!("Part {part}, the answer is {}", store.bounds(day, part)),
+        }
+        for run in runs {
+            let outcome = store
+                .submissions(day, part)
+                .filter(|submission| submission.answer == run.answer)
+                .last()
+                .map_or(String::default(), |submission| {
+                    submission.outcome.to_string()
+                });
+            println!(
+                "  {}  {:<14} {:.8}  {:>10}  {:<16} {outcome}",
+                run::format_timestamp(run.timestamp),
+                run.revision,
+                run.input_hash,
+                format!("{:.2?}", run.runtime),
+                run.answer,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Parses `<day> <part> [answer]` and submits the answer, by default the one
+/// of the last run. `AOC_URL` and `AOC_YEAR` change where it is sent, by
+/// default the 2022 puzzles of the website.
+fn submit(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let day = number::<u32>(&mut args, "submit")?;
+    if !(1..=25).contains(&day) {
+        return Err(invalid(format!("There is no day {day}")));
+    }
+    let part = number::<u32>(&mut args, "submit")?;
+    if !(1..=2).contains(&part) {
+        return Err(invalid("The part is 1 or 2"));
+    }
+    let answer = args.next();
+    no_more_arguments(args)?;
+    let mut store = open_store()?;
+    let answer = match answer {
+        Some(answer) => answer,
+        None => {
+            let answer = store
+                .last_answer(day, part)
+                .ok_or_else(|| invalid(format!("Missing answer, or run day {day} first")))?;
+            println!("Submitting {answer}");
+            answer.to_owned()
+        }
+    };
+
+    let endpoint = Endpoint {
+        url: std::env::var("AOC_URL").unwrap_or_else(|_| "https://adventofcode.com".to_owned()),
+        year: match std::env::var("AOC_YEAR") {
+            Ok(year) => year.parse().map_err(|_| invalid("AOC_YEAR isn't a year"))?,
+            Err(_) => 2022,
+        },
+        session: std::env::var("AOC_SESSION").map_err(|_| Error::MissingSession)?,
+    };
+    let outcome = submit::submit(&endpoint, &mut store, day, part, &answer)?;
+    match outcome {
+        Outcome::Right => println!("That's the right answer!"),
+        Outcome::AlreadySolved => println!("This part is already solved"),
+        Outcome::RateLimited(Some(wait)) => println!("Too many answers, wait {wait}s"),
+        Outcome::RateLimited(None) => println!("Too many answers, wait a bit"),
+        wrong => println!("That's {wrong}, the answer is {}", store.bounds(day, part)),
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("gen") => generate(args)?,
+        Some("run") => run(args)?,
+        Some("history") => history(args)?,
+        Some("submit") => submit(args)?,
+        Some(command) => return Err(invalid(format!("Unknown command {command}")).into()),
+        None => return Err(invalid("Missing command").into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// The answers printed by a day on lines of their own, `Part 1: <answer>`
+/// then `Part 2: <answer>`. Fails unless there is exactly one non-empty
+/// answer for each part, so nothing else is taken for an answer.
+pub fn answers(day: u32, output: &str) -> Result<Vec<(u32, String)>, Error> {
+    let mut answers: [Option<String>; 2] = Default::default();
+    for line in output.lines() {
+        let Some((part, answer)) = line
+            .strip_prefix("Part ")
+            .and_then(|line| line.split_once(':'))
+        else {
+            continue;
+        };
+        let invalid = |reason: &str| Error::Run(format!("day{day} printed {line:?}, {reason}"));
+        let slot = match part {
+            "1" => &mut answers[0],
+            "2" => &mut answers[1],
+            _ => return Err(invalid("there are only parts 1 and 2")),
+        };
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Err(invalid("without an answer"));
+        }
+        if slot.is_some() {
+            return Err(invalid("the part was already answered"));
+        }
+        *slot = Some(answer.to_owned());
+    }
+    (1..)
+        .zip(answers)
+        .map(|(part, answer)| {
+            answer
+                .map(|answer| (part, answer))
+                .ok_or_else(|| Error::Run(format!("day{day} printed no answer for part {part}")))
+        })
+        .collect()
+}
+
+/// Identifies the inputs a day reads, the `src/input*.txt` files, with a
+/// FNV-1a hash of their names and contents
+pub fn input_hash(day_directory: &Path) -> Result<String, Error> {
+    let mut inputs = fs::read_dir(day_directory.join("src"))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    inputs.retain(|path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("input") && name.ends_with(".txt"))
+    });
+    inputs.sort();
+    let mut hash = 0xcbf29ce484222325u64;
+    for input in inputs {
+        let name = input.file_name().unwrap_or_default().as_encoded_bytes();
+        for byte in name.iter().chain(&fs::read(&input)?) {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+    Ok(format!("{hash:016x}"))
+}
+
+/// The commit the code is at, marked `-dirty` when it has uncommitted
+/// changes, or `unknown` outside of git
+pub fn revision(directory: &Path) -> String {
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(directory)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+    match (
+        git(&["rev-parse", "--short", "HEAD"]),
+        git(&["status", "--porcelain", "."]),
+    ) {
+        (Some(commit), Some(changes)) if !changes.is_empty() => format!("{commit}-dirty"),
+        (Some(commit), _) => commit,
+        _ => "unknown".to_owned(),
+    }
+}
+
+/// Builds a day in release mode, then runs it from its directory, where it
+/// finds its input. Returns what it printed and how long it took, the build
+/// not included.
+pub fn run_day(day_directory: &Path, day: u32) -> Result<(String, Duration), Error> {
+    let build = Command::new("cargo")
+        .args(["build", "--release", "--quiet"])
+        .current_dir(day_directory)
+        .status()?;
+    if !build.success() {
+        return Err(Error::Run(format!("day{day} doesn't build")));
+    }
+    let start = Instant::now();
+    let output = Command::new(day_directory.join(format!("target/release/day{day}")))
+        .current_dir(day_directory)
+        .output()?;
+    let runtime = start.elapsed();
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Run(format!("day{day} failed: {}", error.trim())));
+    }
+    Ok((
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        runtime,
+    ))
+}
+
+/// Formats seconds since the Unix epoch as a UTC date and time
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86400, timestamp % 86400);
+    // Days since 0000-03-01, in eras of 400 years, so leap days end years
+    let days = days + 719468;
+    let (era, day_of_era) = (days / 146097, days % 146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = (month_from_march + 2) % 12 + 1;
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{answers, format_timestamp, input_hash};
+    use std::fs;
+
+    #[test]
+    fn answers_of_outputs() {
+        let answers_of = |output| {
+            answers(7, output).map(|answers| {
+                answers
+                    .into_iter()
+                    .map(|(part, answer)| format!("{part}:{answer}"))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let reason = |output| answers_of(output).unwrap_err().to_string();
+        assert_eq!(
+            answers_of("Part 1: 71124\nPart 2: 204639\n").unwrap(),
+            ["1:71124", "2:204639"]
+        );
+        assert_eq!(
+            answers_of(
+                "Part 1: 1325919\nUsed storage: 42036703\nNeeded storage: 2036703\n\
+                 Smallest directory at \"/a\"\nPart 2: 2050735\n2050735\t/a\n"
+            )
+            .unwrap(),
+            ["1:1325919", "2:2050735"]
+        );
+        assert_eq!(
+            answers_of("Part 2: VLCWHTDSZ\nPart 1:  TBVFVDZPN \n").unwrap(),
+            ["1:TBVFVDZPN", "2:VLCWHTDSZ"]
+        );
+        // Other numbers are never taken for an answer
+        assert_eq!(
+            reason("Total size: 1325919\nPart 2: 2050735\n"),
+            "Run failed: day7 printed no answer for part 1"
+        );
+        assert_eq!(
+            reason("Part 1: 1\nPart 1: 2\nPart 2: 3\n"),
+            "Run failed: day7 printed \"Part 1: 2\", the part was already answered"
+        );
+        assert_eq!(
+            reason("Part 1:\nPart 2: 3\n"),
+            "Run failed: day7 printed \"Part 1:\", without an answer"
+        );
+        assert_eq!(
+            reason("Part 3: 1\n"),
+            "Run failed: day7 printed \"Part 3: 1\", there are only parts 1 and 2"
+        );
+    }
+
+    #[test]
+    fn input_hashes() {
+        let directory = std::env::temp_dir().join(format!("aoc-run-{}", std::process::id()));
+        fs::create_dir_all(directory.join("src")).unwrap();
+        fs::write(directory.join("src/input.txt"), "1\n2\n").unwrap();
+        fs::write(directory.join("src/sample_input.txt"), "3\n").unwrap();
+        let first = input_hash(&directory).unwrap();
+        fs::write(directory.join("src/sample_input.txt"), "4\n").unwrap();
+        let samples_changed = input_hash(&directory).unwrap();
+        fs::write(directory.join("src/input2.txt"), "").unwrap();
+        let input_added = input_hash(&directory).unwrap();
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(first.len(), 16);
+        assert_eq!(first, samples_changed);
+        assert_ne!(first, input_added);
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(1670000000), "2022-12-02 16:53");
+        assert_eq!(format_timestamp(951782400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(4107542399), "2100-02-28 23:59");
+    }
+}
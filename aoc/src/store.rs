@@ -0,0 +1,394 @@
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    json::{self, Value},
+    submit::Outcome,
+    Error,
+};
+
+/// Seconds since the Unix epoch, when things are recorded
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// An answer sent to the server and what it replied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submission {
+    pub day: u32,
+    pub part: u32,
+    pub answer: String,
+    pub outcome: Outcome,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl Submission {
+    fn to_json(&self) -> Value {
+        Value::Object(vec![
+            ("day".to_owned(), self.day.into()),
+            ("part".to_owned(), self.part.into()),
+            ("answer".to_owned(), self.answer.as_str().into()),
+            (
+                "outcome".to_owned(),
+                self.outcome.to_string().as_str().into(),
+            ),
+            ("timestamp".to_owned(), self.timestamp.into()),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            day: value.get("day")?.as_u64()?.try_into().ok()?,
+            part: value.get("part")?.as_u64()?.try_into().ok()?,
+            answer: value.get("answer")?.as_str()?.to_owned(),
+            outcome: Outcome::from_str(value.get("outcome")?.as_str()?).ok()?,
+            timestamp: value.get("timestamp")?.as_u64()?,
+        })
+    }
+}
+
+/// An answer computed by a day's solution. Both parts come from the same
+/// run, so they share its runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub day: u32,
+    pub part: u32,
+    pub answer: String,
+    /// Identifies the input the answer is for, see [`crate::run::input_hash`]
+    pub input_hash: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// The commit of the code, see [`crate::run::revision`]
+    pub revision: String,
+    pub runtime: Duration,
+}
+
+impl Run {
+    fn to_json(&self) -> Value {
+        Value::Object(vec![
+            ("day".to_owned(), self.day.into()),
+            ("part".to_owned(), self.part.into()),
+            ("answer".to_owned(), self.answer.as_str().into()),
+            ("input_hash".to_owned(), self.input_hash.as_str().into()),
+            ("timestamp".to_owned(), self.timestamp.into()),
+            ("revision".to_owned(), self.revision.as_str().into()),
+            ("runtime".to_owned(), self.runtime.as_secs_f64().into()),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            day: value.get("day")?.as_u64()?.try_into().ok()?,
+            part: value.get("part")?.as_u64()?.try_into().ok()?,
+            answer: value.get("answer")?.as_str()?.to_owned(),
+            input_hash: value.get("input_hash")?.as_str()?.to_owned(),
+            timestamp: value.get("timestamp")?.as_u64()?,
+            revision: value.get("revision")?.as_str()?.to_owned(),
+            runtime: Duration::try_from_secs_f64(value.get("runtime")?.as_f64()?).ok()?,
+        })
+    }
+}
+
+/// What the failed submissions of a part tell about its answer, both ends
+/// being excluded
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub low: Option<i128>,
+    pub high: Option<i128>,
+}
+
+impl Bounds {
+    pub fn contains(&self, answer: i128) -> bool {
+        self.low.is_none_or(|low| low < answer) && self.high.is_none_or(|high| answer < high)
+    }
+}
+
+impl Display for Bounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.low, self.high) {
+            (Some(low), Some(high)) => write!(f, "between {low} and {high}"),
+            (Some(low), None) => write!(f, "above {low}"),
+            (None, Some(high)) => write!(f, "below {high}"),
+            (None, None) => f.write_str("unknown"),
+        }
+    }
+}
+
+/// Everything known about the answers, kept in a JSON file with one record
+/// per line
+#[derive(Debug, Default)]
+pub struct Store {
+    path: PathBuf,
+    submissions: Vec<Submission>,
+    runs: Vec<Run>,
+}
+
+impl Store {
+    /// Reads the store, a missing file being an empty one
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    path,
+                    ..Self::default()
+                })
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let document = json::parse(&text)?;
+        Ok(Self {
+            path,
+            submissions: records(&document, "submissions", Submission::from_json)?,
+            runs: records(&document, "runs", Run::from_json)?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the store to a temporary file first so it is never left half
+    /// written
+    pub fn save(&self) -> Result<(), Error> {
+        let submissions = self.submissions.iter().map(Submission::to_json);
+        let runs = self.runs.iter().map(Run::to_json);
+        let text = pretty(&[
+            ("submissions", submissions.collect()),
+            ("runs", runs.collect()),
+        ]);
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+
+    /// The submissions of a part, oldest first
+    pub fn submissions(&self, day: u32, part: u32) -> impl Iterator<Item = &Submission> + Clone {
+        self.submissions
+            .iter()
+            .filter(move |submission| submission.day == day && submission.part == part)
+    }
+
+    pub fn record(&mut self, submission: Submission) {
+        self.submissions.push(submission);
+    }
+
+    /// The runs of a day, oldest first
+    pub fn runs(&self, day: u32) -> impl Iterator<Item = &Run> {
+        self.runs.iter().filter(move |run| run.day == day)
+    }
+
+    pub fn record_run(&mut self, run: Run) {
+        self.runs.push(run);
+    }
+
+    /// The answer of the last run of a part
+    pub fn last_answer(&self, day: u32, part: u32) -> Option<&str> {
+        self.runs(day)
+            .filter(|run| run.part == part)
+            .last()
+            .map(|run| run.answer.as_str())
+    }
+
+    /// The answer the server accepted for a part
+    pub fn right_answer(&self, day: u32, part: u32) -> Option<&str> {
+        self.submissions(day, part)
+            .find(|submission| submission.outcome == Outcome::Right)
+            .map(|submission| submission.answer.as_str())
+    }
+
+    /// The highest answer that was too low and the lowest one that was too
+    /// high
+    pub fn bounds(&self, day: u32, part: u32) -> Bounds {
+        let mut bounds = Bounds::default();
+        for submission in self.submissions(day, part) {
+            let Ok(answer) = submission.answer.parse::<i128>() else {
+                continue;
+            };
+            match submission.outcome {
+                Outcome::TooLow => bounds.low = bounds.low.max(Some(answer)),
+                Outcome::TooHigh => {
+                    bounds.high = Some(bounds.high.map_or(answer, |high| high.min(answer)))
+                }
+                _ => {}
+            }
+        }
+        bounds
+    }
+
+    /// Why an answer can't be right, if the submissions tell: it isn't the
+    /// accepted one, it was wrong before, or it is outside the bounds
+    pub fn known_wrong(&self, day: u32, part: u32, answer: &str) -> Option<String> {
+        if let Some(right) = self.right_answer(day, part) {
+            return (answer != right).then(|| format!("the accepted answer is {right}"));
+        }
+        if let Some(wrong) = self
+            .submissions(day, part)
+            .find(|submission| submission.answer == answer && submission.outcome.is_wrong())
+        {
+            return Some(format!("{answer} was already {}", wrong.outcome));
+        }
+        let bounds = self.bounds(day, part);
+        match answer.parse::<i128>() {
+            Ok(number) if !bounds.contains(number) => Some(format!(
+                "{answer} is outside the known bounds, the answer is {bounds}"
+            )),
+            _ => None,
+        }
+    }
+
+    /// Rejects an answer not worth sending: it is empty, the part is solved,
+    /// or it is known to be wrong
+    pub fn check(&self, day: u32, part: u32, answer: &str) -> Result<(), Error> {
+        if answer.is_empty() {
+            return Err(Error::Rejected("the answer is empty".to_owned()));
+        }
+        if let Some(right) = self.right_answer(day, part) {
+            return Err(Error::Rejected(format!(
+                "day {day} part {part} was already solved with {right}"
+            )));
+        }
+        match self.known_wrong(day, part, answer) {
+            Some(reason) => Err(Error::Rejected(reason)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads the records of an array of the store, a missing array being empty
+fn records<T>(
+    document: &Value,
+    name: &str,
+    from_json: impl Fn(&Value) -> Option<T>,
+) -> Result<Vec<T>, Error> {
+    let Some(values) = document.get(name) else {
+        return Ok(Vec::default());
+    };
+    let values = values
+        .as_array()
+        .ok_or_else(|| Error::InvalidStore(format!("{name} isn't an array")))?;
+    values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            from_json(value).ok_or_else(|| Error::InvalidStore(format!("{name} #{index}")))
+        })
+        .collect()
+}
+
+/// An object of arrays with one element per line, so the file diffs well
+fn pretty(arrays: &[(&str, Vec<Value>)]) -> String {
+    let arrays = arrays
+        .iter()
+        .map(|(name, values)| {
+            let values = values
+                .iter()
+                .map(|value| format!("\n    {value}"))
+                .collect::<Vec<_>>();
+            format!("  {}: [{}\n  ]", Value::from(*name), values.join(","))
+        })
+        .collect::<Vec<_>>();
+    format!("{{\n{}\n}}\n", arrays.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bounds, Run, Store, Submission};
+    use crate::{submit::Outcome, Error};
+    use std::time::Duration;
+
+    fn submission(answer: &str, outcome: Outcome) -> Submission {
+        Submission {
+            day: 1,
+            part: 2,
+            answer: answer.to_owned(),
+            outcome,
+            timestamp: 1670000000,
+        }
+    }
+
+    fn run(answer: &str) -> Run {
+        Run {
+            day: 1,
+            part: 2,
+            answer: answer.to_owned(),
+            input_hash: "00ff00ff00ff00ff".to_owned(),
+            timestamp: 1670000000,
+            revision: "1073de1-dirty".to_owned(),
+            runtime: Duration::from_millis(125),
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        let mut store = Store::default();
+        assert_eq!(store.bounds(1, 2), Bounds::default());
+        store.record(submission("50", Outcome::TooLow));
+        store.record(submission("90", Outcome::TooHigh));
+        store.record(submission("70", Outcome::TooHigh));
+        store.record(submission("60", Outcome::TooLow));
+        store.record(submission("65", Outcome::Wrong));
+        let bounds = store.bounds(1, 2);
+        assert_eq!(bounds.to_string(), "between 60 and 70");
+        assert_eq!(store.bounds(1, 1).to_string(), "unknown");
+        assert!(bounds.contains(61) && !bounds.contains(60) && !bounds.contains(70));
+
+        let rejected = |store: &Store, answer| match store.check(1, 2, answer) {
+            Err(Error::Rejected(reason)) => reason,
+            result => panic!("Unexpected {result:?}"),
+        };
+        assert_eq!(
+            rejected(&store, "75"),
+            "75 is outside the known bounds, the answer is between 60 and 70"
+        );
+        assert_eq!(rejected(&store, "65"), "65 was already wrong");
+        assert_eq!(rejected(&store, ""), "the answer is empty");
+        assert!(store.check(1, 2, "66").is_ok());
+        assert!(store.check(1, 2, "ABC").is_ok());
+        assert_eq!(store.known_wrong(1, 2, "66"), None);
+        store.record(submission("66", Outcome::Right));
+        assert_eq!(
+            rejected(&store, "67"),
+            "day 1 part 2 was already solved with 66"
+        );
+        assert_eq!(store.known_wrong(1, 2, "66"), None);
+        assert_eq!(
+            store.known_wrong(1, 2, "67").unwrap(),
+            "the accepted answer is 66"
+        );
+    }
+
+    #[test]
+    fn save_and_open() {
+        let path = std::env::temp_dir().join(format!("aoc-store-{}.json", std::process::id()));
+        let mut store = Store::open(&path).unwrap();
+        store.record(submission("50", Outcome::TooLow));
+        store.record(submission("\"quoted\"", Outcome::RateLimited(None)));
+        store.record_run(run("50"));
+        store.record_run(run("51"));
+        store.save().unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let reopened = Store::open(&path);
+        std::fs::write(&path, "{\"submissions\": [{\"day\": 1}]}").unwrap();
+        let invalid = Store::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(text.lines().count(), 10);
+        let reopened = reopened.unwrap();
+        assert_eq!(reopened.submissions, store.submissions);
+        assert_eq!(reopened.runs, store.runs);
+        assert_eq!(reopened.runs(1).count(), 2);
+        assert_eq!(reopened.runs(2).count(), 0);
+        assert!(matches!(invalid, Err(Error::InvalidStore(_))));
+    }
+}
@@ -0,0 +1,399 @@
+use std::{
+    fmt::{Display, Write as _},
+    io::{Read, Write},
+    net::TcpStream,
+    process::{Command, Stdio},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    store::{now, Store, Submission},
+    Error,
+};
+
+const USER_AGENT: &str = concat!("aoc/", env!("CARGO_PKG_VERSION"));
+
+/// How long to wait for the server
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the server replied to an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Right,
+    Wrong,
+    TooHigh,
+    TooLow,
+    /// The part was solved before, the answer wasn't checked
+    AlreadySolved,
+    /// Answers were sent too quickly, with how many seconds to wait if known
+    RateLimited(Option<u64>),
+}
+
+impl Outcome {
+    /// Whether the server checked the answer and it is wrong
+    pub fn is_wrong(&self) -> bool {
+        matches!(self, Self::Wrong | Self::TooHigh | Self::TooLow)
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Right => "right",
+            Self::Wrong => "wrong",
+            Self::TooHigh => "too high",
+            Self::TooLow => "too low",
+            Self::AlreadySolved => "already solved",
+            Self::RateLimited(_) => "rate limited",
+        })
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = Error;
+
+    /// The names written by `Display`, the wait of a rate limit isn't kept
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "right" => Ok(Self::Right),
+            "wrong" => Ok(Self::Wrong),
+            "too high" => Ok(Self::TooHigh),
+            "too low" => Ok(Self::TooLow),
+            "already solved" => Ok(Self::AlreadySolved),
+            "rate limited" => Ok(Self::RateLimited(None)),
+            s => Err(Error::InvalidStore(format!("unknown outcome {s:?}"))),
+        }
+    }
+}
+
+/// Reads the wait of "You have 1m 30s left to wait" in seconds
+fn wait(body: &str) -> Option<u64> {
+    let start = body.find("You have ")? + "You have ".len();
+    let end = start + body[start..].find(" left to wait")?;
+    body[start..end]
+        .split_whitespace()
+        .try_fold(0u64, |total, part| {
+            let (number, unit) = [('h', 3600), ('m', 60), ('s', 1)]
+                .into_iter()
+                .find_map(|(suffix, unit)| Some((part.strip_suffix(suffix)?, unit)))?;
+            total.checked_add(number.parse::<u64>().ok()?.checked_mul(unit)?)
+        })
+}
+
+/// Finds what the page returned after posting an answer says, `None` for a
+/// page that isn't about an answer
+pub fn parse_response(body: &str) -> Option<Outcome> {
+    if body.contains("That's the right answer") {
+        Some(Outcome::Right)
+    } else if body.contains("That's not the right answer") {
+        Some(if body.contains("too high") {
+            Outcome::TooHigh
+        } else if body.contains("too low") {
+            Outcome::TooLow
+        } else {
+            Outcome::Wrong
+        })
+    } else if body.contains("You gave an answer too recently") {
+        Some(Outcome::RateLimited(wait(body)))
+    } else if body.contains("You don't seem to be solving the right level") {
+        Some(Outcome::AlreadySolved)
+    } else {
+        None
+    }
+}
+
+/// Percent-encodes a value of a form
+fn form_encode(value: &str) -> String {
+    let mut encoded = String::default();
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+/// Where answers are posted, `{url}/{year}/day/{day}/answer` with the
+/// session cookie of the website
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub url: String,
+    pub year: u32,
+    pub session: String,
+}
+
+impl Endpoint {
+    /// Posts an answer and returns the page sent back. Plain HTTP is spoken
+    /// directly, HTTPS goes through `curl`.
+    pub fn post_answer(&self, day: u32, part: u32, answer: &str) -> Result<String, Error> {
+        // The cookie ends up in a header, nothing may break out of it
+        if self.session.is_empty() || !self.session.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(Error::Http("Invalid session cookie".to_owned()));
+        }
+        let url = format!(
+            "{}/{}/day/{day}/answer",
+            self.url.trim_end_matches('/'),
+            self.year
+        );
+        let body = format!("level={part}&answer={}", form_encode(answer));
+        if let Some(location) = url.strip_prefix("http://") {
+            http_post(location, &self.session, &body)
+        } else if url.starts_with("https://") {
+            curl_post(&url, &self.session, &body)
+        } else {
+            Err(Error::Http(format!("Unsupported URL {url}")))
+        }
+    }
+}
+
+/// Posts a form over HTTP/1.0, so the response is never chunked and ends
+/// with the connection
+fn http_post(location: &str, session: &str, body: &str) -> Result<String, Error> {
+    let (host, path) = location
+        .find('/')
+        .map_or((location, "/"), |slash| location.split_at(slash));
+    let address = if host.contains(':') {
+        host.to_owned()
+    } else {
+        format!("{host}:80")
+    };
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "POST {path} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: {USER_AGENT}\r\n\
+         Cookie: session={session}\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+         Content-Length: {}\r\n\r\n{body}",
+        body.len()
+    )?;
+    let mut response = Vec::default();
+    stream.read_to_end(&mut response)?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| Error::Http("Malformed response".to_owned()))?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| Error::Http("Malformed status line".to_owned()))?;
+    if !(200..300).contains(&status) {
+        return Err(Error::Http(format!("Status {status}")));
+    }
+    Ok(body.to_owned())
+}
+
+/// Posts a form with `curl`, the cookie is passed on stdin to keep it out of
+/// the process list
+fn curl_post(url: &str, session: &str, body: &str) -> Result<String, Error> {
+    let timeout = TIMEOUT.as_secs().to_string();
+    let mut curl = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", &timeout])
+        .args([
+            "--user-agent",
+            USER_AGENT,
+            "--config",
+            "-",
+            "--data",
+            body,
+            url,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::Http(format!("HTTPS needs curl: {e}")))?;
+    if let Some(mut stdin) = curl.stdin.take() {
+        writeln!(stdin, "cookie = \"session={session}\"")?;
+    }
+    let output = curl.wait_with_output()?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Http(error.trim().to_owned()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Sends an answer unless the store already knows it is wrong, then records
+/// what the server replied
+pub fn submit(
+    endpoint: &Endpoint,
+    store: &mut Store,
+    day: u32,
+    part: u32,
+    answer: &str,
+) -> Result<Outcome, Error> {
+    let answer = answer.trim();
+    store.check(day, part, answer)?;
+    let response = endpoint.post_answer(day, part, answer)?;
+    let outcome = parse_response(&response)
+        .ok_or_else(|| Error::Http("The response doesn't say if the answer is right".to_owned()))?;
+    store.record(Submission {
+        day,
+        part,
+        answer: answer.to_owned(),
+        outcome,
+        timestamp: now(),
+    });
+    store.save()?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{form_encode, parse_response, submit, Endpoint, Outcome};
+    use crate::{store::Store, Error};
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    const RIGHT: &str = "<article><p>That's the right answer!  You are one gold star closer to \
+                         collecting enough star fruit.</p></article>";
+    const TOO_HIGH: &str = "<article><p>That's not the right answer; your answer is too high.  \
+                            Please wait one minute before trying again.</p></article>";
+    const TOO_LOW: &str = "<article><p>That's not the right answer; your answer is too low.</p>\
+                           </article>";
+    const RATE_LIMITED: &str = "<article><p>You gave an answer too recently; you have to wait \
+                                after submitting an answer before trying again.  You have 1m 5s \
+                                left to wait. <a href=\"/2022/day/7\">[Return to Day 7]</a></p>\
+                                </article>";
+
+    /// Answers each connection with the next response, then returns the
+    /// requests it received
+    fn mock_server(responses: Vec<(u16, &'static str)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::default();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::default();
+                let mut length = 0;
+                loop {
+                    let mut line = String::default();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.strip_prefix("Content-Length: ") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut form = vec![0; length];
+                reader.read_exact(&mut form).unwrap();
+                request.push_str(&String::from_utf8(form).unwrap());
+                requests.push(request);
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(parse_response(RIGHT), Some(Outcome::Right));
+        assert_eq!(parse_response(TOO_HIGH), Some(Outcome::TooHigh));
+        assert_eq!(parse_response(TOO_LOW), Some(Outcome::TooLow));
+        assert_eq!(
+            parse_response(
+                "That's not the right answer.  Curiously, it's the right answer for someone else"
+            ),
+            Some(Outcome::Wrong)
+        );
+        assert_eq!(
+            parse_response(RATE_LIMITED),
+            Some(Outcome::RateLimited(Some(65)))
+        );
+        assert_eq!(
+            parse_response("You gave an answer too recently. You have 40s left to wait."),
+            Some(Outcome::RateLimited(Some(40)))
+        );
+        assert_eq!(
+            parse_response("You gave an answer too recently."),
+            Some(Outcome::RateLimited(None))
+        );
+        assert_eq!(
+            parse_response(
+                "You don't seem to be solving the right level.  Did you already complete it?"
+            ),
+            Some(Outcome::AlreadySolved)
+        );
+        assert_eq!(parse_response("<html>Log in</html>"), None);
+        assert_eq!(form_encode("a b&c=d/é"), "a%20b%26c%3Dd%2F%C3%A9");
+    }
+
+    #[test]
+    fn submit_to_mock_server() {
+        let (url, server) = mock_server(vec![
+            (200, TOO_HIGH),
+            (200, TOO_LOW),
+            (200, RATE_LIMITED),
+            (200, RIGHT),
+            (500, "Internal error"),
+            (200, "<html>Log in</html>"),
+        ]);
+        let endpoint = Endpoint {
+            url,
+            year: 2022,
+            session: "53616c7465645f5f".to_owned(),
+        };
+        let path = std::env::temp_dir().join(format!("aoc-submit-{}.json", std::process::id()));
+        let mut store = Store::open(&path).unwrap();
+        let mut submit = |part, answer| submit(&endpoint, &mut store, 7, part, answer);
+
+        assert_eq!(submit(1, "100").unwrap(), Outcome::TooHigh);
+        // Known to be wrong, the server isn't asked
+        assert!(matches!(submit(1, "150"), Err(Error::Rejected(_))));
+        assert!(matches!(submit(1, "100"), Err(Error::Rejected(_))));
+        assert_eq!(submit(1, " 10\n").unwrap(), Outcome::TooLow);
+        assert_eq!(submit(1, "50").unwrap(), Outcome::RateLimited(Some(65)));
+        assert_eq!(submit(1, "50").unwrap(), Outcome::Right);
+        assert!(matches!(submit(1, "60"), Err(Error::Rejected(_))));
+        assert!(matches!(submit(2, "1"), Err(Error::Http(e)) if e == "Status 500"));
+        assert!(matches!(submit(2, "1"), Err(Error::Http(_))));
+
+        let requests = server.join().unwrap();
+        let reopened = Store::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(requests.len(), 6);
+        assert!(requests[0].starts_with("POST /2022/day/7/answer HTTP/1.0\r\n"));
+        assert!(requests[0].contains("\r\nCookie: session=53616c7465645f5f\r\n"));
+        assert!(requests[0].ends_with("\r\n\r\nlevel=1&answer=100"));
+        assert!(requests[1].ends_with("\r\n\r\nlevel=1&answer=10"));
+        let reopened = reopened.unwrap();
+        assert_eq!(reopened.submissions(7, 1).count(), 4);
+        assert_eq!(reopened.bounds(7, 1).to_string(), "between 10 and 100");
+        assert_eq!(reopened.submissions(7, 2).count(), 0);
+    }
+
+    #[test]
+    fn invalid_endpoints() {
+        let mut endpoint = Endpoint {
+            url: "ftp://example.com".to_owned(),
+            year: 2022,
+            session: "abc".to_owned(),
+        };
+        assert!(
+            matches!(endpoint.post_answer(1, 1, "1"), Err(Error::Http(e)) if e.starts_with("Unsupported URL"))
+        );
+        endpoint.session = "abc\r\nX-Injected: 1".to_owned();
+        assert!(
+            matches!(endpoint.post_answer(1, 1, "1"), Err(Error::Http(e)) if e == "Invalid session cookie")
+        );
+    }
+}
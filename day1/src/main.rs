@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::{io::Read, path::Path};
+use std::{cmp::Reverse, collections::BinaryHeap, io::Read, path::Path};
 
 pub type Calorie = u32;
 
@@ -45,39 +45,99 @@ fn read_input(input: impl AsRef<Path>) -> Result<Vec<Elf>, Error> {
     let mut f = File::open(input)?;
     let mut buf = String::default();
     f.read_to_string(&mut buf)?;
+    parse_elves(&buf)
+}
+
+/// Parses the inventories, one snack per line and blank lines between the
+/// elves. The last elf doesn't need a blank line after it.
+fn parse_elves(buf: &str) -> Result<Vec<Elf>, Error> {
     let mut result = Vec::default();
     let mut elf = Elf::default();
-    for line in buf.split('\n') {
-        if line.is_empty() {
-            result.push(elf);
-            elf = Elf::default();
+    for line in buf.lines() {
+        if line.trim().is_empty() {
+            if !elf.0.is_empty() {
+                result.push(std::mem::take(&mut elf));
+            }
         } else {
             let calroie = line.trim().parse::<Calorie>()?;
             elf.add_to_inventory(calroie);
         }
     }
+    if !elf.0.is_empty() {
+        result.push(elf);
+    }
     Ok(result)
 }
 
+/// The calories carried by the `count` elves carrying the most, only keeping
+/// the best elves so far instead of sorting them all
+fn top_calories(elves: &[Elf], count: usize) -> Calorie {
+    let mut best = BinaryHeap::with_capacity(count + 1);
+    for elf in elves {
+        best.push(Reverse(elf.total_calories()));
+        if best.len() > count {
+            best.pop();
+        }
+    }
+    best.into_iter().map(|Reverse(calories)| calories).sum()
+}
+
 fn main() {
     match read_input("./src/input.txt") {
-        Ok(mut elves) => {
-            elves.sort_by_key(|elf| std::cmp::Reverse(elf.total_calories()));
-            let best_elf = elves.get(0).unwrap();
-            println!(
-                "The elf carrying the most calroies has {} calories",
-                best_elf.total_calories()
-            );
+        Ok(elves) => {
+            // Calories carried by the elf carrying the most
+            println!("Part 1: {}", top_calories(&elves, 1));
 
-            let total_three_best: Calorie =
-                elves.iter().take(3).map(|elf| elf.total_calories()).sum();
-            println!(
-                "The sum of the three Elves carrying the most is {} calories",
-                total_three_best
-            );
+            // Calories carried by the three elves carrying the most
+            let total_three_best = top_calories(&elves, 3);
+            println!("Part 2: {total_three_best}");
         }
         Err(err) => {
             println!("Failed to parse file {err}");
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_elves, top_calories, Calorie};
+    use aoc::{fuzz, gen, rng::Rng};
+
+    #[test]
+    fn sample() {
+        let input = include_str!("sample_input.txt");
+        assert!(!input.ends_with("\n\n"));
+        let elves = parse_elves(input).unwrap();
+        assert_eq!(elves.len(), 5);
+        assert_eq!(top_calories(&elves, 1), 24000);
+        assert_eq!(top_calories(&elves, 3), 45000);
+        let elves = parse_elves("1\r\n\r\n\n2\r\n3").unwrap();
+        assert_eq!(elves.len(), 2);
+        assert!(parse_elves("1\nx\n").is_err());
+    }
+
+    #[test]
+    fn fuzz_parse_elves() {
+        fuzz::check(&[include_str!("sample_input.txt")], |input| {
+            let _ = parse_elves(input);
+        });
+    }
+
+    #[test]
+    fn matches_sorting() {
+        for seed in 0..200 {
+            let size = Rng::new(seed).between(0, 50);
+            let elves = parse_elves(&gen::inventories(&mut Rng::new(seed), size)).unwrap();
+            // The original solution
+            let mut totals = elves
+                .iter()
+                .map(|elf| elf.total_calories())
+                .collect::<Vec<_>>();
+            totals.sort_by_key(|total| std::cmp::Reverse(*total));
+            for count in 0..5 {
+                let expected = totals.iter().take(count).sum::<Calorie>();
+                assert_eq!(top_calories(&elves, count), expected);
+            }
+        }
+    }
+}
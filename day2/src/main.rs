@@ -3,6 +3,8 @@ use std::{cmp::Ordering, fs::File, io::Read, path::Path, str::FromStr};
 #[derive(Debug)]
 pub enum Error {
     InvalidShape(String),
+    /// A line that isn't two shapes separated by whitespace
+    InvalidRound(String),
     IO(std::io::Error),
 }
 impl std::error::Error for Error {}
@@ -10,6 +12,7 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::InvalidShape(shape) => f.write_fmt(format_args!("Invalid shape {shape}")),
+            Self::InvalidRound(line) => f.write_fmt(format_args!("Invalid round {line:?}")),
             Self::IO(err) => f.write_fmt(format_args!("IO error {err}")),
         }
     }
@@ -110,24 +113,39 @@ impl Round {
     }
 }
 
-fn read_input(path: impl AsRef<Path>) -> Result<Vec<Round>, Error> {
+fn read_input(path: impl AsRef<Path>) -> Result<String, Error> {
     let mut f = File::open(path)?;
     let mut buff = String::default();
     f.read_to_string(&mut buff)?;
+    Ok(buff)
+}
+
+/// How the second column of the strategy guide is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guide {
+    /// The shape to play, as in the first puzzle
+    Shapes,
+    /// The outcome to aim for, as in the second puzzle
+    Outcomes,
+}
 
+/// Parses one round per non-blank line, the opponent's shape then what the
+/// guide says to do
+fn parse_rounds(buff: &str, guide: Guide) -> Result<Vec<Round>, Error> {
     let mut rounds = Vec::default();
 
-    for line in buff.lines() {
+    for line in buff.lines().filter(|line| !line.trim().is_empty()) {
         let mut words = line.split_whitespace();
-        let shape1 = Shape::from_str(words.next().unwrap())?;
-        // uncomment for the first puzzle
-        // let shape2 = Shape::from_str(words.next().unwrap())?;
-        // seconds puzzle
-        let shape2 = match words.next().unwrap() {
-            "X" => shape1.what_to_play(Ordering::Less),
-            "Y" => shape1.what_to_play(Ordering::Equal),
-            "Z" => shape1.what_to_play(Ordering::Greater),
-            _ => unreachable!(),
+        let (Some(shape1), Some(shape2), None) = (words.next(), words.next(), words.next()) else {
+            return Err(Error::InvalidRound(line.to_owned()));
+        };
+        let shape1 = Shape::from_str(shape1)?;
+        let shape2 = match (guide, shape2) {
+            (Guide::Shapes, shape) => Shape::from_str(shape)?,
+            (Guide::Outcomes, "X") => shape1.what_to_play(Ordering::Less),
+            (Guide::Outcomes, "Y") => shape1.what_to_play(Ordering::Equal),
+            (Guide::Outcomes, "Z") => shape1.what_to_play(Ordering::Greater),
+            (Guide::Outcomes, outcome) => return Err(Error::InvalidShape(outcome.to_owned())),
         };
         let round = Round::new(shape1, shape2);
         rounds.push(round);
@@ -135,14 +153,86 @@ fn read_input(path: impl AsRef<Path>) -> Result<Vec<Round>, Error> {
     Ok(rounds)
 }
 
+/// The total score of the rounds of the guide read one way or the other
+fn total_score(buff: &str, guide: Guide) -> Result<u32, Error> {
+    let rounds = parse_rounds(buff, guide)?;
+    Ok(rounds.iter().map(|round| round.score() as u32).sum::<u32>())
+}
+
 fn main() {
-    match read_input("./src/input.txt") {
-        Ok(rounds) => {
-            let total_score = rounds.iter().map(|round| round.score() as u32).sum::<u32>();
-            println!("{}", total_score)
+    let scores = read_input("./src/input.txt").and_then(|buff| {
+        Ok((
+            total_score(&buff, Guide::Shapes)?,
+            total_score(&buff, Guide::Outcomes)?,
+        ))
+    });
+    match scores {
+        Ok((first, second)) => {
+            println!("Part 1: {first}");
+            println!("Part 2: {second}");
         }
         Err(err) => {
             println!("Failed to read input {err}")
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rounds, total_score, Error, Guide, Round, Shape};
+    use aoc::{fuzz, gen, rng::Rng};
+    use std::{cmp::Ordering, str::FromStr};
+
+    #[test]
+    fn sample() {
+        let sample = include_str!("sample_input.txt");
+        assert_eq!(total_score(sample, Guide::Shapes).unwrap(), 15);
+        assert_eq!(total_score(sample, Guide::Outcomes).unwrap(), 12);
+        for guide in [Guide::Shapes, Guide::Outcomes] {
+            assert!(matches!(
+                parse_rounds("A\n", guide),
+                Err(Error::InvalidRound(_))
+            ));
+            assert!(matches!(
+                parse_rounds("A Y Z\n", guide),
+                Err(Error::InvalidRound(_))
+            ));
+            assert!(matches!(
+                parse_rounds("A W\n", guide),
+                Err(Error::InvalidShape(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn fuzz_parse_rounds() {
+        fuzz::check(&[include_str!("sample_input.txt")], |input| {
+            let _ = parse_rounds(input, Guide::Shapes);
+            let _ = parse_rounds(input, Guide::Outcomes);
+        });
+    }
+
+    /// Scores a round with arithmetic on the shape indices instead of
+    /// comparing shapes: 1 beats 0, 2 beats 1 and 0 beats 2
+    fn naive_score(opponent: usize, you: usize) -> u8 {
+        let outcome = [3, 6, 0][(you + 3 - opponent) % 3];
+        you as u8 + 1 + outcome
+    }
+
+    #[test]
+    fn matches_arithmetic() {
+        let guide = gen::strategy_guide(&mut Rng::new(2), 1000);
+        for line in guide.lines() {
+            let (opponent, you) = line.split_once(' ').unwrap();
+            let index =
+                |shape: &str, first: char| shape.chars().next().unwrap() as usize - first as usize;
+            let expected = naive_score(index(opponent, 'A'), index(you, 'X'));
+            let opponent = Shape::from_str(opponent).unwrap();
+            let shape = Shape::from_str(you).unwrap();
+            for ordering in [Ordering::Less, Ordering::Equal, Ordering::Greater] {
+                assert_eq!(opponent.what_to_play(ordering).cmp(&opponent), ordering);
+            }
+            assert_eq!(Round::new(opponent, shape).score(), expected, "{line}");
+        }
+    }
+}
@@ -1,11 +1,59 @@
-use std::{fs::File, io::Read, path::Path};
+#[cfg(test)]
 static CHARACTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
 
-fn second_problem(path: impl AsRef<Path>) -> Result<u32, std::io::Error> {
-    let mut f = File::open(path)?;
-    let mut buff = String::default();
-    f.read_to_string(&mut buff)?;
+/// The priority of an item type, `None` for anything that isn't one
+fn priority(item: u8) -> Option<u32> {
+    match item {
+        b'a'..=b'z' => Some(u32::from(item - b'a') + 1),
+        b'A'..=b'Z' => Some(u32::from(item - b'A') + 27),
+        _ => None,
+    }
+}
+
+/// The item types of a rucksack or a compartment as a set of bits, the bit
+/// of an item type being its priority
+fn item_set(items: &[u8]) -> u64 {
+    items
+        .iter()
+        .filter_map(|item| priority(*item))
+        .fold(0, |set, priority| set | 1 << priority)
+}
+
+/// The sum of the priorities of the item types in a set
+fn set_priority(set: u64) -> u32 {
+    (1..=52).filter(|priority| set & 1 << priority != 0).sum()
+}
+
+/// The sum of the priorities of the item types in both compartments of each
+/// rucksack
+fn rucksack_priorities(buff: &str) -> u32 {
+    buff.lines()
+        .map(|line| {
+            let (first, second) = line.as_bytes().split_at(line.len() / 2);
+            set_priority(item_set(first) & item_set(second))
+        })
+        .sum()
+}
+
+/// The sum of the priorities of the badges, the item types carried by all
+/// three elves of a group
+fn group_priorities(buff: &str) -> u32 {
+    buff.lines()
+        .collect::<Vec<_>>()
+        .chunks(3)
+        .map(|group| {
+            let badges = group
+                .iter()
+                .fold(u64::MAX, |set, line| set & item_set(line.as_bytes()));
+            set_priority(badges)
+        })
+        .sum()
+}
 
+/// The original solution to the second problem, kept to check
+/// [`group_priorities`] against
+#[cfg(test)]
+fn second_problem(buff: &str) -> u32 {
     let mut lines = buff.lines();
     let mut total_priorities = 0;
     loop {
@@ -27,14 +75,13 @@ fn second_problem(path: impl AsRef<Path>) -> Result<u32, std::io::Error> {
         let group_priority = CHARACTERS.find(common_letter).unwrap() as u32 + 1;
         total_priorities += group_priority;
     }
-    Ok(total_priorities)
+    total_priorities
 }
 
-fn first_problem(path: impl AsRef<Path>) -> Result<u32, std::io::Error> {
-    let mut f = File::open(path)?;
-    let mut buff = String::default();
-    f.read_to_string(&mut buff)?;
-
+/// The original solution to the first problem, kept to check
+/// [`rucksack_priorities`] against
+#[cfg(test)]
+fn first_problem(buff: &str) -> u32 {
     let mut total_priorities = 0;
     for line in buff.lines() {
         let length = line.len();
@@ -61,20 +108,55 @@ fn first_problem(path: impl AsRef<Path>) -> Result<u32, std::io::Error> {
         let ruckstack_priority = CHARACTERS.find(common_char).unwrap() as u32 + 1;
         total_priorities += ruckstack_priority;
     }
-    Ok(total_priorities)
+    total_priorities
 }
 
 fn main() {
     match (
-        first_problem("./src/input.txt"),
-        second_problem("./src/input2.txt"),
+        std::fs::read_to_string("./src/input.txt"),
+        std::fs::read_to_string("./src/input2.txt"),
     ) {
-        (Ok(total_priorities), Ok(total_group_priorities)) => {
-            println!("The sum of the priorities is {total_priorities}");
-            println!("The sum of the priorities per group is {total_group_priorities}");
+        (Ok(input), Ok(input2)) => {
+            let total_priorities = rucksack_priorities(&input);
+            let total_group_priorities = group_priorities(&input2);
+            println!("Part 1: {total_priorities}");
+            println!("Part 2: {total_group_priorities}");
         }
         _ => {
             println!("Failed to read the file");
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{first_problem, group_priorities, rucksack_priorities, second_problem};
+    use aoc::{fuzz, gen, rng::Rng};
+
+    #[test]
+    fn sample() {
+        assert_eq!(rucksack_priorities(include_str!("sample_input.txt")), 157);
+        assert_eq!(group_priorities(include_str!("sample_input2.txt")), 70);
+    }
+
+    #[test]
+    fn matches_original() {
+        for seed in 0..100 {
+            let rucksacks = gen::rucksacks(&mut Rng::new(seed), 20);
+            assert_eq!(rucksack_priorities(&rucksacks), first_problem(&rucksacks));
+            assert_eq!(group_priorities(&rucksacks), second_problem(&rucksacks));
+        }
+    }
+
+    #[test]
+    fn fuzz_priorities() {
+        let corpus = [
+            include_str!("sample_input.txt"),
+            include_str!("sample_input2.txt"),
+        ];
+        fuzz::check(&corpus, |input| {
+            rucksack_priorities(input);
+            group_priorities(input);
+        });
+    }
+}
@@ -2,14 +2,26 @@ use std::{path::Path, str::FromStr};
 
 #[derive(Debug)]
 pub enum Error {
-    PairParseError(&'static str),
+    /// The given input couldn't be parsed as a section range or a group of them
+    PairParseError {
+        input: String,
+        reason: &'static str,
+    },
+    /// Wraps a parse error with the line (starting at 1) it happened at
+    Line {
+        line: usize,
+        error: Box<Error>,
+    },
     IO(std::io::Error),
 }
 impl std::error::Error for Error {}
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::PairParseError(e) => f.write_fmt(format_args!("Failed to parse input {e}")),
+            Error::PairParseError { input, reason } => {
+                f.write_fmt(format_args!("Failed to parse input {input:?}: {reason}"))
+            }
+            Error::Line { line, error } => f.write_fmt(format_args!("Line {line}: {error}")),
             Error::IO(e) => f.write_fmt(format_args!("Failed due to IO {e}")),
         }
     }
@@ -20,12 +32,40 @@ impl From<std::io::Error> for Error {
     }
 }
 
-#[derive(Debug)]
+impl Error {
+    fn pair(input: &str, reason: &'static str) -> Self {
+        Self::PairParseError {
+            input: input.to_owned(),
+            reason,
+        }
+    }
+}
+
+/// An inclusive range of sections, `a-b`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Pair(u32, u32);
 
 impl Pair {
-    pub fn range(&self) -> std::ops::Range<u32> {
-        self.0..(self.1 + 1)
+    pub fn start(&self) -> u32 {
+        self.0
+    }
+
+    pub fn end(&self) -> u32 {
+        self.1
+    }
+
+    pub fn range(&self) -> std::ops::RangeInclusive<u32> {
+        self.0..=self.1
+    }
+
+    /// Whether every section of `other` is part of this range
+    pub fn contains(&self, other: &Pair) -> bool {
+        self.0 <= other.0 && other.1 <= self.1
+    }
+
+    /// Whether both ranges share at least one section
+    pub fn overlaps(&self, other: &Pair) -> bool {
+        self.0 <= other.1 && other.0 <= self.1
     }
 }
 
@@ -34,47 +74,226 @@ impl FromStr for Pair {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (first_part, second_part) = s
             .split_once('-')
-            .ok_or(Error::PairParseError("Pair doesn't contain '-'"))?;
+            .ok_or_else(|| Error::pair(s, "Pair doesn't contain '-'"))?;
         let first_value = first_part
+            .trim()
             .parse::<u32>()
-            .map_err(|_| Error::PairParseError("Not a valid number"))?;
+            .map_err(|_| Error::pair(s, "Not a valid number"))?;
         let second_value = second_part
+            .trim()
             .parse::<u32>()
-            .map_err(|_| Error::PairParseError("Not a valid number"))?;
+            .map_err(|_| Error::pair(s, "Not a valid number"))?;
+        if first_value > second_value {
+            return Err(Error::pair(s, "Range start is after its end"));
+        }
 
         Ok(Pair(first_value, second_value))
     }
 }
 
+/// The section ranges assigned to a group of elves, `a-b,c-d,...`
+#[derive(Debug)]
+pub struct Group(Vec<Pair>);
+
+impl Group {
+    pub fn pairs(&self) -> &[Pair] {
+        &self.0
+    }
+
+    /// Whether any two elves of the group have overlapping assignments
+    pub fn any_overlap(&self) -> bool {
+        let mut pairs = self.0.clone();
+        pairs.sort_by_key(|pair| pair.start());
+        // Once sorted by start, a range overlaps a previous one iff it starts
+        // before the furthest end seen so far
+        let mut furthest_end = None;
+        for pair in pairs {
+            if furthest_end.is_some_and(|end| pair.start() <= end) {
+                return true;
+            }
+            furthest_end = furthest_end.max(Some(pair.end()));
+        }
+        false
+    }
+
+    /// Whether the assignment of one elf contains the assignments of all the others.
+    ///
+    /// A group of a single elf trivially satisfies it.
+    pub fn one_contains_all(&self) -> bool {
+        let Some(start) = self.0.iter().map(Pair::start).min() else {
+            return false;
+        };
+        let end = self.0.iter().map(Pair::end).max().unwrap();
+        self.0.contains(&Pair(start, end))
+    }
+}
+
+impl FromStr for Group {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pairs = s
+            .split(',')
+            .map(|pair| {
+                if pair.trim().is_empty() {
+                    Err(Error::pair(s, "Empty section range"))
+                } else {
+                    Pair::from_str(pair.trim())
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Group(pairs))
+    }
+}
+
+/// Parses every non-blank line as a [`Group`]
+fn parse_groups(input: &str) -> Result<Vec<Group>, Error> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            Group::from_str(line).map_err(|error| Error::Line {
+                line: index + 1,
+                error: Box::new(error),
+            })
+        })
+        .collect()
+}
+
 fn read_input(path: impl AsRef<Path>, full_overlap: bool) -> Result<u32, Error> {
     let buff = std::fs::read_to_string(path)?;
-    let lines = buff.lines();
-    let mut duplicated_ones = 0;
-    for line in lines {
-        let (first_pair, second_pair) = line.trim().split_once(',').unwrap();
-        let (first_pair, second_pair) = (Pair::from_str(first_pair)?, Pair::from_str(second_pair)?);
-        if full_overlap {
-            if first_pair.range().all(|e| second_pair.range().contains(&e))
-                || second_pair.range().all(|e| first_pair.range().contains(&e))
-            {
-                duplicated_ones += 1;
+    let duplicated_ones = parse_groups(&buff)?
+        .iter()
+        .filter(|group| {
+            if full_overlap {
+                group.one_contains_all()
+            } else {
+                group.any_overlap()
             }
-        } else if first_pair.range().any(|e| second_pair.range().contains(&e))
-            || second_pair.range().any(|e| first_pair.range().contains(&e))
-        {
-            duplicated_ones += 1;
+        })
+        .count();
+    Ok(duplicated_ones as u32)
+}
+
+fn main() {
+    match (
+        read_input("./src/input.txt", true),
+        read_input("./src/input.txt", false),
+    ) {
+        (Ok(fully_contained), Ok(duplicated_ones)) => {
+            println!("Part 1: {fully_contained}");
+            println!("Part 2: {duplicated_ones}");
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            println!("Failed to parse input {err}");
         }
     }
-    Ok(duplicated_ones)
 }
 
-fn main() {
-    match read_input("./src/input.txt", false) {
-        Ok(duplicated_ones) => {
-            println!("There are {duplicated_ones} duplicated efforts");
+#[cfg(test)]
+mod tests {
+    use super::{parse_groups, Error, Group, Pair};
+    use aoc::{fuzz, gen, rng::Rng};
+    use std::{collections::HashSet, str::FromStr};
+
+    /// Whether any two elves share a section, comparing every section
+    fn naive_overlap(group: &Group) -> bool {
+        let mut seen = HashSet::new();
+        group.pairs().iter().any(|pair| {
+            !pair
+                .range()
+                .collect::<HashSet<_>>()
+                .iter()
+                .all(|s| seen.insert(*s))
+        })
+    }
+
+    /// Whether one elf has every section of the group
+    fn naive_contains_all(group: &Group) -> bool {
+        let sections = group
+            .pairs()
+            .iter()
+            .flat_map(Pair::range)
+            .collect::<HashSet<_>>();
+        group
+            .pairs()
+            .iter()
+            .any(|pair| sections.iter().all(|s| pair.range().contains(s)))
+    }
+
+    #[test]
+    fn sample() {
+        let groups = parse_groups(include_str!("sample_input.txt")).unwrap();
+        assert_eq!(groups.iter().filter(|g| g.one_contains_all()).count(), 2);
+        assert_eq!(groups.iter().filter(|g| g.any_overlap()).count(), 4);
+    }
+
+    #[test]
+    fn whitespace_and_groups() {
+        let group = Group::from_str(" 2 - 4 , 6-8,3-3 ").unwrap();
+        assert_eq!(group.pairs(), &[Pair(2, 4), Pair(6, 8), Pair(3, 3)]);
+        assert!(group.any_overlap());
+        assert!(!group.one_contains_all());
+
+        let group = Group::from_str("1-10,2-3,5-9").unwrap();
+        assert!(group.one_contains_all());
+        // 1-9 overlaps 5-6 even though 11-12 is apart from both
+        assert!(Group::from_str("1-9,5-6,11-12").unwrap().any_overlap());
+        // No two of these touch, whatever their order
+        assert!(!Group::from_str("1-2,11-12,5-6").unwrap().any_overlap());
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse_groups("2-4,6-8\n\n2-4,x-8\n").unwrap_err();
+        assert!(matches!(err, Error::Line { line: 3, .. }));
+        assert_eq!(
+            err.to_string(),
+            "Line 3: Failed to parse input \"x-8\": Not a valid number"
+        );
+        assert!(matches!(
+            Group::from_str("2-4,,6-8"),
+            Err(Error::PairParseError { .. })
+        ));
+        assert!(Pair::from_str("5-3").is_err());
+        assert!(Pair::from_str("53").is_err());
+        assert!(Pair::from_str("1-2-3").is_err());
+        let pair = Pair::from_str("4294967294-4294967295").unwrap();
+        assert_eq!(pair.range().count(), 2);
+    }
+
+    #[test]
+    fn matches_naive_sections() {
+        let pairs = gen::section_pairs(&mut Rng::new(4), 1000);
+        let mut groups = parse_groups(&pairs).unwrap();
+        // Larger groups over fewer sections
+        let mut rng = Rng::new(4);
+        for _ in 0..2000 {
+            let pairs = (0..rng.between(1, 6))
+                .map(|_| {
+                    let start = rng.between(1, 20) as u32;
+                    Pair(start, rng.between(start as usize, 20) as u32)
+                })
+                .collect();
+            groups.push(Group(pairs));
         }
-        Err(err) => {
-            println!("Failed to parse input {:#?}", err);
+        for group in groups {
+            assert_eq!(group.any_overlap(), naive_overlap(&group), "{group:?}");
+            assert_eq!(
+                group.one_contains_all(),
+                naive_contains_all(&group),
+                "{group:?}"
+            );
         }
     }
+
+    #[test]
+    fn fuzz_parse_groups() {
+        fuzz::check(&[include_str!("sample_input.txt")], |input| {
+            for group in parse_groups(input).iter().flatten() {
+                group.any_overlap();
+                group.one_contains_all();
+            }
+        });
+    }
 }
@@ -0,0 +1,192 @@
+use crate::{
+    stacks::{MoveError, Stacks},
+    Error, Step,
+};
+
+/// A crane model, which decides how the crates of a [`Step`] are moved
+pub trait Crane {
+    fn name(&self) -> String;
+
+    /// The maximum number of crates the crane can lift at once
+    fn capacity(&self) -> usize;
+
+    /// Applies a single step to the stacks, lifting at most
+    /// [`capacity`](Crane::capacity) crates at a time and keeping the order
+    /// of the lifted crates. The stacks are left untouched on failure.
+    fn execute(&self, stacks: &mut Stacks, step: &Step) -> Result<(), MoveError> {
+        stacks.check_move(step.quantity as usize, step.from, step.to)?;
+        let mut remaining = step.quantity as usize;
+        while remaining > 0 {
+            let lifted = remaining.min(self.capacity());
+            stacks.move_many(lifted, step.from, step.to)?;
+            remaining -= lifted;
+        }
+        Ok(())
+    }
+
+    /// Applies all the steps, stopping at the first one that can't be applied.
+    ///
+    /// With `create_missing`, destination stacks that don't exist yet are
+    /// created instead of failing. A stack created for a step that fails is
+    /// removed again, so the stacks are left as they were before that step.
+    fn rearrange(
+        &self,
+        stacks: &mut Stacks,
+        steps: &[Step],
+        create_missing: bool,
+    ) -> Result<(), Error> {
+        for (index, step) in steps.iter().enumerate() {
+            let created = create_missing && stacks.stack(step.to).is_none();
+            if created {
+                stacks.add_stack(step.to);
+            }
+            if let Err(error) = self.execute(stacks, step) {
+                if created {
+                    stacks.remove_stack(step.to);
+                }
+                return Err(Error::InvalidMove { index, error });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Moves the crates one at a time
+#[derive(Debug, Default)]
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
+
+    fn capacity(&self) -> usize {
+        1
+    }
+
+    fn execute(&self, stacks: &mut Stacks, step: &Step) -> Result<(), MoveError> {
+        stacks.check_move(step.quantity as usize, step.from, step.to)?;
+        for _ in 0..step.quantity {
+            stacks.move_one(step.from, step.to)?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves all the crates of a step at once
+#[derive(Debug, Default)]
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+}
+
+/// Moves up to a fixed number of crates at a time
+#[derive(Debug)]
+pub struct LimitedCrane(usize);
+
+impl LimitedCrane {
+    /// # Panics
+    ///
+    /// If the capacity is zero as such a crane couldn't move anything.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "A crane must be able to lift a crate");
+        Self(capacity)
+    }
+}
+
+impl Crane for LimitedCrane {
+    fn name(&self) -> String {
+        format!("Crane lifting up to {} crates", self.0)
+    }
+
+    fn capacity(&self) -> usize {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crane, CrateMover9000, CrateMover9001, LimitedCrane};
+    use crate::{
+        stacks::{MoveError, Stacks},
+        Error, Step,
+    };
+    use std::str::FromStr;
+
+    fn stacks() -> Stacks {
+        let mut stacks = Stacks::default();
+        for (id, labels) in [(1, "ABCDE"), (2, "")] {
+            stacks.add_stack(id);
+            for label in labels.chars() {
+                stacks.push(id, crate::stacks::Crate::new(label.to_string()));
+            }
+        }
+        stacks
+    }
+
+    fn moved(crane: &dyn Crane) -> String {
+        let mut stacks = stacks();
+        crane
+            .execute(&mut stacks, &Step::from_str("move 5 from 1 to 2").unwrap())
+            .unwrap();
+        stacks.to_string().lines().map(|l| &l[4..]).collect()
+    }
+
+    #[test]
+    fn models() {
+        assert_eq!(moved(&CrateMover9000), "[A][B][C][D][E] 2 ");
+        assert_eq!(moved(&CrateMover9001), "[E][D][C][B][A] 2 ");
+        assert_eq!(moved(&LimitedCrane::new(2)), "[A][C][B][E][D] 2 ");
+    }
+
+    #[test]
+    fn rearrange() {
+        let steps =
+            ["move 1 from 1 to 3", "move 2 from 2 to 1"].map(|step| Step::from_str(step).unwrap());
+        let mut stacks = stacks();
+        let err = CrateMover9001
+            .rearrange(&mut stacks, &steps, false)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidMove {
+                index: 0,
+                error: MoveError::UnknownStack(3)
+            }
+        ));
+        let err = CrateMover9001
+            .rearrange(&mut stacks, &steps, true)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidMove {
+                index: 1,
+                error: MoveError::EmptyStack(2)
+            }
+        ));
+        assert_eq!(stacks.tops(), "DE");
+
+        // The stack created for a failing step doesn't stay behind
+        let steps = [Step::from_str("move 9 from 1 to 4").unwrap()];
+        let before = stacks.clone();
+        let err = CrateMover9001
+            .rearrange(&mut stacks, &steps, true)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidMove {
+                index: 0,
+                error: MoveError::EmptyStack(1)
+            }
+        ));
+        assert_eq!(stacks, before);
+        assert_eq!(stacks.stack(4), None);
+    }
+}
@@ -1,24 +1,63 @@
+mod crane;
+mod planner;
+mod simulation;
+mod stacks;
+
+use crane::{Crane, CrateMover9000, CrateMover9001, LimitedCrane};
+use simulation::Simulation;
+use stacks::{Crate, MoveError, Stacks};
 use std::{
-    collections::{BTreeMap, VecDeque},
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
 #[derive(Debug)]
 pub enum Error {
     ParseStepError(String),
+    /// The position, starting at 1, of a malformed part of the drawing
+    ParseDrawingError {
+        line: usize,
+        column: usize,
+        reason: &'static str,
+    },
+    /// The step at that index in the list of steps couldn't be applied
+    InvalidMove {
+        index: usize,
+        error: MoveError,
+    },
+    /// The target layout can't be reached
+    NoPlan(String),
+    InvalidArgument(String),
+    IO(std::io::Error),
 }
-
-#[derive(Debug, Clone)]
-pub struct Crate(String);
-
-impl std::fmt::Display for Crate {
+impl std::error::Error for Error {}
+impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.0)
+        match self {
+            Self::ParseStepError(e) => f.write_fmt(format_args!("Failed to parse step {e}")),
+            Self::ParseDrawingError {
+                line,
+                column,
+                reason,
+            } => f.write_fmt(format_args!(
+                "Failed to parse drawing at {line}:{column}: {reason}"
+            )),
+            Self::InvalidMove { index, error } => {
+                f.write_fmt(format_args!("Invalid step #{index}: {error}"))
+            }
+            Self::NoPlan(e) => f.write_fmt(format_args!("Failed to plan a rearrangement {e}")),
+            Self::InvalidArgument(e) => f.write_fmt(format_args!("Invalid argument {e}")),
+            Self::IO(e) => f.write_fmt(format_args!("IO error {e}")),
+        }
+    }
+}
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::IO(e)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Step {
     quantity: u32,
     from: usize,
@@ -28,101 +67,444 @@ pub struct Step {
 impl FromStr for Step {
     type Err = Error;
 
+    /// Parses `move N from N to N`
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if !s.starts_with("move") {
-            return Err(Error::ParseStepError(
-                "Step doesn't start with move".to_owned(),
-            ));
-        }
-
-        let line = s.split_whitespace().collect::<Vec<_>>();
-        let quantity = line.get(1).unwrap().parse::<u32>().unwrap();
-        let from = line.get(3).unwrap().parse::<usize>().unwrap();
-        let to = line.get(5).unwrap().parse::<usize>().unwrap();
+        let error = |reason: &str| Error::ParseStepError(format!("{s:?}: {reason}"));
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        let ["move", quantity, "from", from, "to", to] = words[..] else {
+            return Err(error("Expected move N from N to N"));
+        };
+        let quantity = quantity
+            .parse::<u32>()
+            .map_err(|_| error("Invalid quantity"))?;
+        let from = from
+            .parse::<usize>()
+            .map_err(|_| error("Invalid source stack"))?;
+        let to = to
+            .parse::<usize>()
+            .map_err(|_| error("Invalid destination stack"))?;
 
         Ok(Self { quantity, from, to })
     }
 }
 
-fn parse_stacks(input: Vec<&'_ str>) -> BTreeMap<usize, VecDeque<Crate>> {
-    let line_length = input.first().unwrap().len();
-    let mut stacks: BTreeMap<usize, VecDeque<Crate>> = BTreeMap::new();
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "move {} from {} to {}",
+            self.quantity, self.from, self.to
+        ))
+    }
+}
 
-    for j in 0..input.len() {
+/// Parses the drawing of the stacks, the last line being the stack numbers.
+///
+/// Crates are matched to the stack number they are drawn above, so trimmed
+/// lines, multi-digit stack numbers and multi-character labels are supported.
+fn parse_stacks(input: &[&str]) -> Result<Stacks, Error> {
+    let error = |line: usize, column: usize, reason| Error::ParseDrawingError {
+        line: line + 1,
+        column: column + 1,
+        reason,
+    };
+    let (footer, rows) = input
+        .split_last()
+        .ok_or_else(|| error(0, 0, "The drawing is empty"))?;
+    let mut stacks = Stacks::default();
+    // The stack numbers and where they are in the footer
+    let mut columns = Vec::default();
+    let footer = footer.chars().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < footer.len() {
+        if footer[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < footer.len() && !footer[i].is_whitespace() {
+            i += 1;
+        }
+        let id = footer[start..i]
+            .iter()
+            .collect::<String>()
+            .parse::<usize>()
+            .map_err(|_| error(rows.len(), start, "Not a valid stack number"))?;
+        if stacks.ids().any(|other| other == id) {
+            return Err(error(rows.len(), start, "Duplicated stack number"));
+        }
+        stacks.add_stack(id);
+        columns.push((id, start..i));
+    }
+    if columns.is_empty() {
+        return Err(error(rows.len(), 0, "No stack numbers"));
+    }
+
+    // Go through the drawing bottom up so crates are pushed in order
+    for (row, line) in rows.iter().enumerate().rev() {
+        let level = rows.len() - row - 1;
+        let line = line.chars().collect::<Vec<_>>();
         let mut i = 0;
-        let mut column = 1;
-        while i < line_length {
-            let crate_ = &input.get(j).unwrap()[i..i + 3];
-            if !crate_.trim().is_empty() {
-                let crate_ = Crate(crate_.to_owned());
-                let crate_clone = crate_.clone();
-                stacks
-                    .entry(column)
-                    .and_modify(move |e| e.push_back(crate_clone))
-                    .or_insert_with(|| {
-                        let mut vec = VecDeque::new();
-                        vec.push_back(crate_);
-                        vec
-                    });
+        while i < line.len() {
+            if line[i].is_whitespace() {
+                i += 1;
+                continue;
             }
-            column += 1;
-            i += 4;
+            if line[i] != '[' {
+                return Err(error(row, i, "Expected a crate starting with '['"));
+            }
+            let end = line[i..]
+                .iter()
+                .position(|c| *c == ']')
+                .map(|end| i + end)
+                .ok_or_else(|| error(row, i, "Crate isn't closed by ']'"))?;
+            let label = line[i + 1..end].iter().collect::<String>();
+            if label.trim().is_empty() || label.contains('[') {
+                return Err(error(row, i, "Invalid crate label"));
+            }
+            let mut above = columns
+                .iter()
+                .filter(|(_, column)| column.start <= end && i < column.end);
+            let id = match (above.next(), above.next()) {
+                (Some((id, _)), None) => *id,
+                (None, _) => return Err(error(row, i, "Crate isn't above a stack number")),
+                (Some(_), Some(_)) => return Err(error(row, i, "Crate is above several stacks")),
+            };
+            match stacks.height(id) {
+                height if height == level => stacks.push(id, Crate::new(label)),
+                height if height > level => {
+                    return Err(error(row, i, "Several crates above the same stack"))
+                }
+                _ => return Err(error(row, i, "Crate isn't on top of another one")),
+            }
+            i = end + 1;
         }
     }
-    stacks
+    Ok(stacks)
 }
 
-fn read_input(
-    path: impl AsRef<Path>,
-    move_all: bool,
-) -> Result<BTreeMap<usize, VecDeque<Crate>>, std::io::Error> {
-    let buff = std::fs::read_to_string(path)?;
-    let lines = buff.lines();
-
-    let mut stacks_lines = Vec::default();
-    let mut steps = Vec::default();
-    for line in lines {
-        if !line.is_empty() && !line.starts_with("move") {
-            if !line.trim().starts_with('1') {
-                stacks_lines.push(line);
+fn read_input(path: impl AsRef<Path>) -> Result<(Stacks, Vec<Step>), Error> {
+    parse_input(&std::fs::read_to_string(path)?)
+}
+
+/// Parses the drawing, then the steps after the first blank line
+fn parse_input(buff: &str) -> Result<(Stacks, Vec<Step>), Error> {
+    let mut lines = buff.lines();
+
+    let stacks_lines = lines
+        .by_ref()
+        .take_while(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let steps = lines
+        .filter(|line| !line.trim().is_empty())
+        .map(Step::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((parse_stacks(&stacks_lines)?, steps))
+}
+
+#[derive(Default)]
+struct Options {
+    cranes: Vec<Box<dyn Crane>>,
+    /// No crane was given, the two puzzle models answer the two parts
+    puzzle: bool,
+    input: Option<String>,
+    /// Print the drawing after every step
+    trace: bool,
+    /// Write the drawing after every step to a file in that directory
+    frames: Option<PathBuf>,
+    /// Only show the drawing after that many steps
+    step: Option<usize>,
+    /// Only show the drawing that many steps before the end
+    undo: Option<usize>,
+    /// Create the destination stacks that don't exist instead of failing
+    create_missing: bool,
+    /// Print steps rearranging the input stacks into the drawing of that file
+    plan: Option<PathBuf>,
+}
+
+/// Parses `[--crane 9000|9001] [--capacity N] [--trace] [--frames DIR] [--step N] [--undo N]
+/// [--create-missing] [--plan TARGET] [input]`,
+/// without any crane given both puzzle models are used, answering the two parts
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, Error> {
+    let mut options = Options::default();
+    let number = |args: &mut dyn Iterator<Item = String>, flag: &str| {
+        args.next()
+            .and_then(|value| value.parse::<usize>().ok())
+            .ok_or_else(|| Error::InvalidArgument(format!("{flag} expects a number")))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--crane" => match args.next().as_deref() {
+                Some("9000") => options.cranes.push(Box::new(CrateMover9000)),
+                Some("9001") => options.cranes.push(Box::new(CrateMover9001)),
+                model => {
+                    return Err(Error::InvalidArgument(format!(
+                        "unknown crane model {model:?}"
+                    )))
+                }
+            },
+            "--capacity" => match number(&mut args, "--capacity")? {
+                0 => {
+                    return Err(Error::InvalidArgument(
+                        "--capacity must be positive".to_owned(),
+                    ))
+                }
+                capacity => options.cranes.push(Box::new(LimitedCrane::new(capacity))),
+            },
+            "--trace" => options.trace = true,
+            "--frames" => {
+                let directory = args.next().ok_or_else(|| {
+                    Error::InvalidArgument("--frames expects a directory".to_owned())
+                })?;
+                options.frames = Some(directory.into());
+            }
+            "--step" => options.step = Some(number(&mut args, "--step")?),
+            "--undo" => options.undo = Some(number(&mut args, "--undo")?),
+            "--create-missing" => options.create_missing = true,
+            "--plan" => {
+                let target = args.next().ok_or_else(|| {
+                    Error::InvalidArgument("--plan expects a drawing file".to_owned())
+                })?;
+                options.plan = Some(target.into());
             }
-        } else if line.starts_with("move") {
-            steps.push(Step::from_str(line).unwrap());
+            path => options.input = Some(path.to_owned()),
         }
     }
-    let mut stacks = parse_stacks(stacks_lines);
-    for step in steps {
-        let mut i = 0;
-        let mut move_crates = VecDeque::default();
-        while i < step.quantity {
-            let crate_ = stacks.get_mut(&step.from).unwrap().pop_front().unwrap();
-            if move_all {
-                move_crates.push_front(crate_);
-            } else {
-                stacks
-                    .entry(step.to)
-                    .and_modify(|entry| entry.push_front(crate_));
+    if options.cranes.is_empty() {
+        options.cranes = vec![Box::new(CrateMover9000), Box::new(CrateMover9001)];
+        options.puzzle = true;
+    }
+    Ok(options)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = parse_args(std::env::args().skip(1))?;
+    let input = options.input.as_deref().unwrap_or("./src/input.txt");
+    let (stacks, steps) = read_input(input)?;
+    if let Some(target) = options.plan {
+        let [crane] = options.cranes.as_slice() else {
+            return Err(Box::new(Error::InvalidArgument(
+                "--plan needs a single --crane or --capacity".to_owned(),
+            )));
+        };
+        let target = std::fs::read_to_string(target)?;
+        let target = parse_stacks(&target.lines().collect::<Vec<_>>())?;
+        let steps = planner::plan(crane.as_ref(), &stacks, &target)?;
+        print!("{}", planner::serialize(&stacks, &steps));
+        return Ok(());
+    }
+    for (index, crane) in options.cranes.into_iter().enumerate() {
+        let mut simulation =
+            Simulation::new(crane, stacks.clone(), steps.clone(), options.create_missing)?;
+        let name = simulation.crane().name();
+        let total_steps = simulation.steps().len();
+        if let Some(frames) = &options.frames {
+            let directory = frames.join(index.to_string());
+            std::fs::create_dir_all(&directory)?;
+            std::fs::write(directory.join("0000.txt"), simulation.current().to_string())?;
+            for (position, stacks) in simulation.trace() {
+                std::fs::write(
+                    directory.join(format!("{position:04}.txt")),
+                    stacks.to_string(),
+                )?;
             }
-            i += 1;
+            simulation.jump_to(0);
         }
-        if move_all {
-            while let Some(crate_) = move_crates.pop_front() {
-                stacks
-                    .entry(step.to)
-                    .and_modify(|entry| entry.push_front(crate_));
+        if options.trace {
+            println!("{}\n", simulation.current());
+            for (position, stacks) in simulation.trace() {
+                println!("{}\n{stacks}\n", steps[position - 1]);
             }
         }
+        simulation
+            .jump_to(options.step.unwrap_or(total_steps))
+            .ok_or_else(|| Error::InvalidArgument(format!("there are only {total_steps} steps")))?;
+        for _ in 0..options.undo.unwrap_or_default() {
+            simulation.undo().ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "can't undo more than the {} steps done",
+                    options.step.unwrap_or(total_steps)
+                ))
+            })?;
+        }
+        if simulation.position() == total_steps && options.puzzle {
+            println!("Part {}: {}", index + 1, simulation.current().tops());
+        } else if simulation.position() == total_steps {
+            println!("{name}: {}", simulation.current().tops());
+        } else {
+            println!(
+                "{name} after {} steps:\n{}",
+                simulation.position(),
+                simulation.current()
+            );
+        }
     }
-    Ok(stacks)
+    Ok(())
 }
 
-fn main() {
-    let mut input = read_input("./src/input.txt", true).unwrap();
-    for i in 0..(input.len() + 1) {
-        if let Some(crate_) = input.get_mut(&i).and_then(|crates| crates.pop_front()) {
-            print!("{} ", crate_);
-        } else {
-            print!("");
+#[cfg(test)]
+mod tests {
+    use super::{
+        crane::{Crane, CrateMover9000, CrateMover9001, LimitedCrane},
+        parse_input, parse_stacks, Error, Stacks, Step,
+    };
+    use aoc::{fuzz, gen, rng::Rng};
+    use std::str::FromStr;
+
+    fn parse(drawing: &str) -> Result<super::Stacks, Error> {
+        parse_stacks(&drawing.lines().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn ragged_drawing() {
+        let stacks = parse("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3").unwrap();
+        assert_eq!(stacks.tops(), "NDP");
+        assert_eq!(
+            stacks.to_string(),
+            "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+    }
+
+    #[test]
+    fn wide_drawing() {
+        let drawing = "[AB]                                      [C]\n 1   2   3   4   5   6   7   8   9   10   11";
+        let stacks = parse(drawing).unwrap();
+        assert_eq!(stacks.ids().count(), 11);
+        assert_eq!(stacks.top(1).unwrap().label(), "AB");
+        assert_eq!(stacks.top(11).unwrap().label(), "C");
+        // The rendering can be parsed back
+        assert_eq!(parse(&stacks.to_string()).unwrap(), stacks);
+    }
+
+    #[test]
+    fn malformed_drawing() {
+        let error = |drawing| match parse(drawing) {
+            Err(Error::ParseDrawingError {
+                line,
+                column,
+                reason,
+            }) => (line, column, reason),
+            result => panic!("Unexpected {result:?}"),
+        };
+        assert_eq!(error(""), (1, 1, "The drawing is empty"));
+        assert_eq!(error("[A]\n 1 a"), (2, 4, "Not a valid stack number"));
+        assert_eq!(
+            error("[A] x\n 1   2"),
+            (1, 5, "Expected a crate starting with '['")
+        );
+        assert_eq!(error("[A] [B\n 1   2"), (1, 5, "Crate isn't closed by ']'"));
+        assert_eq!(error("[]\n 1"), (1, 1, "Invalid crate label"));
+        assert_eq!(
+            error("    [B]\n 1"),
+            (1, 5, "Crate isn't above a stack number")
+        );
+        assert_eq!(
+            error("[A]\n\n 1"),
+            (1, 1, "Crate isn't on top of another one")
+        );
+    }
+
+    #[test]
+    fn malformed_steps() {
+        let error = |step| match Step::from_str(step) {
+            Err(Error::ParseStepError(e)) => e,
+            result => panic!("Unexpected {result:?}"),
+        };
+        assert_eq!(
+            error("move 1 from 2"),
+            "\"move 1 from 2\": Expected move N from N to N"
+        );
+        assert_eq!(
+            error("move 1 to 2 from 3"),
+            "\"move 1 to 2 from 3\": Expected move N from N to N"
+        );
+        assert_eq!(
+            error("move -1 from 2 to 3"),
+            "\"move -1 from 2 to 3\": Invalid quantity"
+        );
+        assert_eq!(
+            error("move 1 from x to 3"),
+            "\"move 1 from x to 3\": Invalid source stack"
+        );
+        assert_eq!(
+            Step::from_str("  move 3  from 1 to 2 ")
+                .unwrap()
+                .to_string(),
+            "move 3 from 1 to 2"
+        );
+    }
+
+    #[test]
+    fn fuzz_parse_input() {
+        fuzz::check(&[include_str!("sample_input.txt")], |input| {
+            let Ok((stacks, steps)) = parse_input(input) else {
+                return;
+            };
+            let _ = parse(&stacks.to_string());
+            let cranes: [&dyn Crane; 2] = [&CrateMover9000, &CrateMover9001];
+            for crane in cranes {
+                let _ = crane.rearrange(&mut stacks.clone(), &steps, true);
+            }
+        });
+    }
+
+    /// Parses the drawing like the original solution, every stack being 4
+    /// characters wide
+    fn naive_stacks(drawing: &str) -> Vec<Vec<char>> {
+        let lines = drawing.lines().collect::<Vec<_>>();
+        let (footer, rows) = lines.split_last().unwrap();
+        let mut stacks = vec![Vec::default(); footer.split_whitespace().count()];
+        for row in rows.iter().rev() {
+            for (index, cell) in row.as_bytes().chunks(4).enumerate() {
+                if cell[0] == b'[' {
+                    stacks[index].push(cell[1] as char);
+                }
+            }
+        }
+        stacks
+    }
+
+    fn labels(stacks: &Stacks) -> Vec<Vec<char>> {
+        stacks
+            .ids()
+            .map(|id| {
+                let stack = stacks.stack(id).unwrap();
+                stack
+                    .iter()
+                    .flat_map(|crate_| crate_.label().chars())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_naive_cranes() {
+        for seed in 0..200 {
+            let input = gen::rearrangement(&mut Rng::new(seed), 30);
+            let (stacks, steps) = parse_input(&input).unwrap();
+            let (drawing, _) = input.split_once("\n\n").unwrap();
+            assert_eq!(labels(&stacks), naive_stacks(drawing));
+            assert_eq!(parse(&stacks.to_string()).unwrap(), stacks);
+            for capacity in [1, 2, 3, usize::MAX] {
+                let mut naive = naive_stacks(drawing);
+                for step in &steps {
+                    let mut remaining = step.quantity as usize;
+                    while remaining > 0 {
+                        let lifted = remaining.min(capacity);
+                        let from = &mut naive[step.from - 1];
+                        let moved = from.split_off(from.len() - lifted);
+                        naive[step.to - 1].extend(moved);
+                        remaining -= lifted;
+                    }
+                }
+                let crane: Box<dyn Crane> = match capacity {
+                    1 => Box::new(CrateMover9000),
+                    usize::MAX => Box::new(CrateMover9001),
+                    capacity => Box::new(LimitedCrane::new(capacity)),
+                };
+                let mut stacks = stacks.clone();
+                crane.rearrange(&mut stacks, &steps, false).unwrap();
+                assert_eq!(labels(&stacks), naive, "{} on {input}", crane.name());
+            }
         }
     }
 }
@@ -0,0 +1,463 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    rc::Rc,
+};
+
+use crate::{
+    crane::Crane,
+    stacks::{Crate, Stacks},
+    Error, Step,
+};
+
+/// Upper bound of states explored while looking for the shortest plan,
+/// past it a longer plan is built instead
+const MAX_SEARCHED_STATES: usize = 100_000;
+
+/// Past that many crates there are so many possible steps that the search
+/// gives up a couple of steps deep, so it isn't tried at all
+const MAX_SEARCHED_CRATES: usize = 20;
+
+/// Finds steps rearranging `start` into `target` with the given crane.
+///
+/// The shortest plan is searched for first, if the layouts are too large for
+/// that a plan is built one target stack at a time instead.
+pub fn plan(crane: &dyn Crane, start: &Stacks, target: &Stacks) -> Result<Vec<Step>, Error> {
+    if !start.ids().eq(target.ids()) {
+        return Err(Error::NoPlan(
+            "The target doesn't have the same stacks".to_owned(),
+        ));
+    }
+    let labels = |stacks: &Stacks| {
+        let mut labels = stacks
+            .ids()
+            .flat_map(|id| stacks.stack(id).unwrap_or_default())
+            .map(Crate::label)
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        labels.sort();
+        labels
+    };
+    if labels(start) != labels(target) {
+        return Err(Error::NoPlan(
+            "The target doesn't have the same crates".to_owned(),
+        ));
+    }
+    let shortest = (labels(start).len() <= MAX_SEARCHED_CRATES)
+        .then(|| shortest_plan(crane, start, target))
+        .flatten();
+    match shortest {
+        Some(steps) => Ok(steps),
+        None => stack_by_stack_plan(crane, start, target),
+    }
+}
+
+/// Stacks as stored by [`Layouts`], shared by the list of states and the set
+/// of visited ones
+type Layout = Rc<[u16]>;
+
+/// Stacks stored compactly for the search, each crate as the index of its
+/// label in the sorted labels and each stack followed by [`Layouts::END`]
+struct Layouts {
+    ids: Vec<usize>,
+    labels: Vec<String>,
+}
+
+impl Layouts {
+    const END: u16 = u16::MAX;
+
+    /// `None` if there are too many different labels to index them
+    fn new(stacks: &Stacks) -> Option<Self> {
+        let ids = stacks.ids().collect::<Vec<_>>();
+        let mut labels = ids
+            .iter()
+            .flat_map(|&id| stacks.stack(id).unwrap_or_default())
+            .map(|crate_| crate_.label().to_owned())
+            .collect::<Vec<_>>();
+        labels.sort();
+        labels.dedup();
+        (labels.len() < usize::from(Self::END)).then_some(Self { ids, labels })
+    }
+
+    fn encode(&self, stacks: &Stacks) -> Layout {
+        let mut layout = Vec::default();
+        for &id in &self.ids {
+            for crate_ in stacks.stack(id).unwrap_or_default() {
+                let label = self
+                    .labels
+                    .binary_search_by(|label| label.as_str().cmp(crate_.label()));
+                layout.push(label.expect("Every label was indexed") as u16);
+            }
+            layout.push(Self::END);
+        }
+        layout.into()
+    }
+
+    fn decode(&self, layout: &[u16]) -> Stacks {
+        let mut stacks = Stacks::default();
+        let mut ids = self.ids.iter();
+        let mut id = ids.next();
+        for &index in layout {
+            let Some(&current) = id else {
+                break;
+            };
+            if index == Self::END {
+                stacks.add_stack(current);
+                id = ids.next();
+            } else {
+                stacks.push(current, Crate::new(&self.labels[usize::from(index)]));
+            }
+        }
+        stacks
+    }
+}
+
+/// Breadth first search over every possible step, gives up after
+/// [`MAX_SEARCHED_STATES`] states.
+///
+/// Each state is stored once, [compactly](Layouts), with the index of the
+/// state it was reached from.
+fn shortest_plan(crane: &dyn Crane, start: &Stacks, target: &Stacks) -> Option<Vec<Step>> {
+    let layouts = Layouts::new(start)?;
+    let ids = &layouts.ids;
+    let target = layouts.encode(target);
+    // Each visited state with the index of the state and the step it was
+    // reached from
+    let mut states: Vec<(Layout, Option<(usize, Step)>)> = vec![(layouts.encode(start), None)];
+    let mut visited = HashSet::from([Rc::clone(&states[0].0)]);
+    let mut queue = VecDeque::from([0]);
+
+    while let Some(index) = queue.pop_front() {
+        if states[index].0 == target {
+            let mut steps = Vec::default();
+            let mut current = index;
+            while let Some((previous, step)) = &states[current].1 {
+                steps.push(step.clone());
+                current = *previous;
+            }
+            steps.reverse();
+            return Some(steps);
+        }
+        let stacks = layouts.decode(&states[index].0);
+        for &from in ids {
+            for &to in ids {
+                for quantity in 1..=stacks.height(from) {
+                    let step = Step {
+                        quantity: quantity as u32,
+                        from,
+                        to,
+                    };
+                    let mut next = stacks.clone();
+                    if crane.execute(&mut next, &step).is_err() {
+                        continue;
+                    }
+                    let next = layouts.encode(&next);
+                    if visited.contains(&next) {
+                        continue;
+                    }
+                    if states.len() >= MAX_SEARCHED_STATES {
+                        return None;
+                    }
+                    visited.insert(Rc::clone(&next));
+                    queue.push_back(states.len());
+                    states.push((next, Some((index, step))));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Moves `quantity` crates from `from` to `to` such that replaying the
+/// returned steps backwards, with source and destination swapped, restores
+/// the original order
+fn transfer(crane: &dyn Crane, quantity: usize, from: usize, to: usize) -> Vec<Step> {
+    // Moving one at a time reverses the crates, which moving them back undoes.
+    // Otherwise each step must be lifted at once.
+    let chunk = if crane.capacity() == 1 {
+        quantity
+    } else {
+        crane.capacity()
+    };
+    let mut steps = Vec::default();
+    let mut remaining = quantity;
+    while remaining > 0 {
+        let quantity = remaining.min(chunk);
+        steps.push(Step {
+            quantity: quantity as u32,
+            from,
+            to,
+        });
+        remaining -= quantity;
+    }
+    steps
+}
+
+/// Builds the target stacks one after the other from the bottom, bringing
+/// each needed crate on top of its stack.
+///
+/// Crates already at their place are never moved, everything else can be
+/// piled up on other stacks until it's needed.
+fn stack_by_stack_plan(
+    crane: &dyn Crane,
+    start: &Stacks,
+    target: &Stacks,
+) -> Result<Vec<Step>, Error> {
+    let ids = start.ids().collect::<Vec<_>>();
+    let mut stacks = start.clone();
+    let mut steps = Vec::default();
+    let mut apply = |stacks: &mut Stacks, step: Step| {
+        crane
+            .execute(stacks, &step)
+            .expect("Planned steps are always valid");
+        steps.push(step);
+    };
+    // How many crates at the bottom of each stack are at their final place
+    let mut placed = ids
+        .iter()
+        .map(|&id| {
+            let (stack, goal) = (stacks.stack(id).unwrap(), target.stack(id).unwrap());
+            let placed = stack.iter().zip(goal).take_while(|(a, b)| a == b).count();
+            (id, placed)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    for (position, &id) in ids.iter().enumerate() {
+        let goal = target.stack(id).unwrap();
+        loop {
+            // Crates may have landed at their place while piling up others
+            let stack = stacks.stack(id).unwrap();
+            let level = placed[&id]
+                + stack[placed[&id]..]
+                    .iter()
+                    .zip(&goal[placed[&id]..])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+            if level == goal.len() {
+                placed.insert(id, level);
+                break;
+            }
+            let loose = stacks.height(id) - level;
+            if loose > 0 {
+                // Prefer piling up on stacks that are not built yet
+                let other = ids[position + 1..]
+                    .iter()
+                    .chain(&ids[..position])
+                    .next()
+                    .ok_or_else(|| {
+                        Error::NoPlan("A single stack can't be rearranged".to_owned())
+                    })?;
+                apply(
+                    &mut stacks,
+                    Step {
+                        quantity: loose as u32,
+                        from: id,
+                        to: *other,
+                    },
+                );
+            }
+
+            let (source, depth) = ids
+                .iter()
+                .filter(|&&source| source != id)
+                .filter_map(|&source| {
+                    let stack = stacks.stack(source).unwrap();
+                    stack[placed[&source]..]
+                        .iter()
+                        .rev()
+                        .position(|crate_| crate_ == &goal[level])
+                        .map(|depth| (source, depth))
+                })
+                .min_by_key(|(_, depth)| *depth)
+                .expect("Both layouts have the same crates");
+            let transfers = if depth > 0 {
+                let helper = ids
+                    .iter()
+                    .find(|&&helper| helper != id && helper != source)
+                    .ok_or_else(|| {
+                        Error::NoPlan("Digging out a crate needs at least three stacks".to_owned())
+                    })?;
+                transfer(crane, depth, source, *helper)
+            } else {
+                Vec::default()
+            };
+            for step in transfers.iter() {
+                apply(&mut stacks, step.clone());
+            }
+            apply(
+                &mut stacks,
+                Step {
+                    quantity: 1,
+                    from: source,
+                    to: id,
+                },
+            );
+            for step in transfers.into_iter().rev() {
+                apply(
+                    &mut stacks,
+                    Step {
+                        quantity: step.quantity,
+                        from: step.to,
+                        to: step.from,
+                    },
+                );
+            }
+            placed.insert(id, level + 1);
+        }
+    }
+    debug_assert_eq!(&stacks, target);
+    Ok(steps)
+}
+
+/// Renders the stacks and the steps in the puzzle input format
+pub fn serialize(stacks: &Stacks, steps: &[Step]) -> String {
+    let mut output = format!("{stacks}\n\n");
+    for step in steps {
+        output.push_str(&format!("{step}\n"));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan, serialize, stack_by_stack_plan};
+    use crate::{
+        crane::{Crane, CrateMover9000, CrateMover9001, LimitedCrane},
+        parse_input, parse_stacks, read_input,
+        stacks::{Crate, Stacks},
+        Step,
+    };
+    use aoc::{gen, rng::Rng};
+
+    fn stacks(drawing: &str) -> crate::Stacks {
+        parse_stacks(&drawing.lines().collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn shortest() {
+        let start = stacks("    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 ");
+        let target = stacks("        [Z]\n        [N]\n        [D]\n[M] [C] [P]\n 1   2   3 ");
+        let cranes: [Box<dyn Crane>; 2] = [Box::new(CrateMover9000), Box::new(CrateMover9001)];
+        for crane in cranes {
+            let steps = plan(crane.as_ref(), &start, &target).unwrap();
+            assert_eq!(steps.len(), 5);
+            let mut stacks = start.clone();
+            crane.rearrange(&mut stacks, &steps, false).unwrap();
+            assert_eq!(stacks, target);
+            let fallback = stack_by_stack_plan(crane.as_ref(), &start, &target).unwrap();
+            assert!(fallback.len() >= steps.len());
+        }
+    }
+
+    #[test]
+    fn puzzle_sized() {
+        let (start, _) = parse_input(include_str!("input.txt")).unwrap();
+        // The first two stacks swapped
+        let mut target = Stacks::default();
+        for id in start.ids() {
+            let source = match id {
+                1 => 2,
+                2 => 1,
+                id => id,
+            };
+            target.add_stack(id);
+            for crate_ in start.stack(source).unwrap() {
+                target.push(id, crate_.clone());
+            }
+        }
+        let crane = CrateMover9001;
+        let steps = plan(&crane, &start, &target).unwrap();
+        // Far too many crates to search, the plan is built stack by stack
+        assert_eq!(steps, stack_by_stack_plan(&crane, &start, &target).unwrap());
+        let mut stacks = start.clone();
+        crane.rearrange(&mut stacks, &steps, false).unwrap();
+        assert_eq!(stacks, target);
+    }
+
+    #[test]
+    fn round_trip() {
+        let start = stacks("[A] [E] [I]\n[B] [F] [J]\n[C] [G] [K]\n[D] [H] [L]\n 1   2   3 ");
+        let target = stacks("[L] [H] [D]\n[K] [G] [C]\n[J] [F] [B]\n[I] [E] [A]\n 1   2   3 ");
+        let cranes: [Box<dyn Crane>; 3] = [
+            Box::new(CrateMover9000),
+            Box::new(CrateMover9001),
+            Box::new(LimitedCrane::new(2)),
+        ];
+        for crane in cranes {
+            let steps = stack_by_stack_plan(crane.as_ref(), &start, &target).unwrap();
+            // Unique to this run, so concurrent test runs don't share files
+            let path = std::env::temp_dir().join(format!(
+                "day5-plan-{}-{}.txt",
+                std::process::id(),
+                crane.capacity()
+            ));
+            std::fs::write(&path, serialize(&start, &steps)).unwrap();
+            let read = read_input(&path);
+            std::fs::remove_file(path).unwrap();
+            let (mut stacks, steps) = read.unwrap();
+            assert_eq!(stacks, start);
+            crane.rearrange(&mut stacks, &steps, false).unwrap();
+            assert_eq!(stacks, target);
+        }
+    }
+
+    #[test]
+    fn impossible() {
+        let start = stacks("[A] [B]\n 1   2 ");
+        assert!(plan(&CrateMover9001, &start, &stacks("[A] [C]\n 1   2 ")).is_err());
+        assert!(plan(&CrateMover9001, &start, &stacks("[A] [B]\n 1   3 ")).is_err());
+    }
+
+    #[test]
+    fn fallback_reaches_random_targets() {
+        for seed in 0..100 {
+            let input = gen::rearrangement(&mut Rng::new(seed), 20);
+            let (start, steps) = parse_input(&input).unwrap();
+            let cranes: [Box<dyn Crane>; 3] = [
+                Box::new(CrateMover9000),
+                Box::new(CrateMover9001),
+                Box::new(LimitedCrane::new(2)),
+            ];
+            for crane in cranes {
+                let mut target = start.clone();
+                crane.rearrange(&mut target, &steps, false).unwrap();
+                let plan = stack_by_stack_plan(crane.as_ref(), &start, &target).unwrap();
+                let mut stacks = start.clone();
+                crane.rearrange(&mut stacks, &plan, false).unwrap();
+                assert_eq!(stacks, target, "{} on {input}", crane.name());
+            }
+        }
+    }
+
+    #[test]
+    fn shortest_beats_random_steps() {
+        let mut rng = Rng::new(5);
+        for _ in 0..100 {
+            // Few crates so the search always completes
+            let mut start = Stacks::default();
+            for id in 1..=3 {
+                start.add_stack(id);
+            }
+            for label in ["A", "B", "B", "C", "D"] {
+                start.push(rng.between(1, 3), Crate::new(label));
+            }
+            let crane = CrateMover9001;
+            let mut target = start.clone();
+            let mut steps = Vec::default();
+            while steps.len() < 4 {
+                let (from, to) = (rng.between(1, 3), rng.between(1, 3));
+                if from == to || target.height(from) == 0 {
+                    continue;
+                }
+                let quantity = rng.between(1, target.height(from)) as u32;
+                let step = Step { quantity, from, to };
+                crane.execute(&mut target, &step).unwrap();
+                steps.push(step);
+            }
+            let plan = plan(&crane, &start, &target).unwrap();
+            assert!(plan.len() <= steps.len());
+            let mut stacks = start.clone();
+            crane.rearrange(&mut stacks, &plan, false).unwrap();
+            assert_eq!(stacks, target);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{crane::Crane, stacks::Stacks, Error, Step};
+
+/// Replays the steps of a rearrangement one at a time.
+///
+/// Every visited state is kept, so going back or jumping to an already
+/// computed step is free.
+pub struct Simulation {
+    crane: Box<dyn Crane>,
+    steps: Vec<Step>,
+    /// `history[i]` is the state of the stacks after the first `i` steps
+    history: Vec<Stacks>,
+    position: usize,
+    create_missing: bool,
+}
+
+impl Simulation {
+    /// Fails if any of the steps can't be applied,
+    /// see [`Crane::rearrange`] for `create_missing`
+    pub fn new(
+        crane: Box<dyn Crane>,
+        stacks: Stacks,
+        steps: Vec<Step>,
+        create_missing: bool,
+    ) -> Result<Self, Error> {
+        crane.rearrange(&mut stacks.clone(), &steps, create_missing)?;
+        Ok(Self {
+            crane,
+            steps,
+            history: vec![stacks],
+            position: 0,
+            create_missing,
+        })
+    }
+
+    pub fn crane(&self) -> &dyn Crane {
+        self.crane.as_ref()
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// The number of steps applied to reach the current state
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn current(&self) -> &Stacks {
+        &self.history[self.position]
+    }
+
+    /// Applies the next step, returns `None` once all the steps were applied
+    pub fn forward(&mut self) -> Option<&Stacks> {
+        let step = self.steps.get(self.position)?;
+        if self.history.len() == self.position + 1 {
+            let mut stacks = self.current().clone();
+            if self.create_missing {
+                stacks.add_stack(step.to);
+            }
+            self.crane
+                .execute(&mut stacks, step)
+                .expect("Steps are validated when creating the simulation");
+            self.history.push(stacks);
+        }
+        self.position += 1;
+        Some(self.current())
+    }
+
+    /// Reverts the last applied step, returns `None` if at the initial state
+    pub fn undo(&mut self) -> Option<&Stacks> {
+        self.position = self.position.checked_sub(1)?;
+        Some(self.current())
+    }
+
+    /// Moves to the state after the first `position` steps, going forward or
+    /// back as needed. Returns `None` if there are not as many steps.
+    pub fn jump_to(&mut self, position: usize) -> Option<&Stacks> {
+        if position > self.steps.len() {
+            return None;
+        }
+        if position < self.history.len() {
+            self.position = position;
+        } else {
+            self.position = self.history.len() - 1;
+            while self.position < position {
+                self.forward();
+            }
+        }
+        Some(self.current())
+    }
+
+    /// Iterates over the remaining states, each with the number of steps
+    /// applied to reach it
+    pub fn trace(&mut self) -> impl Iterator<Item = (usize, Stacks)> + '_ {
+        std::iter::from_fn(move || {
+            let stacks = self.forward()?.clone();
+            Some((self.position, stacks))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Simulation;
+    use crate::{crane::CrateMover9001, read_input};
+
+    #[test]
+    fn replay() {
+        let (stacks, steps) = read_input("./src/sample_input.txt").unwrap();
+        let mut simulation =
+            Simulation::new(Box::new(CrateMover9001), stacks.clone(), steps, false).unwrap();
+        let tops = simulation
+            .trace()
+            .map(|(_, stacks)| stacks.tops())
+            .collect::<Vec<_>>();
+        assert_eq!(tops, ["DCP", "CD", "CD", "MCD"]);
+        assert_eq!(simulation.undo().unwrap().tops(), "CD");
+        assert_eq!(simulation.jump_to(0), Some(&stacks));
+        assert!(simulation.undo().is_none());
+        assert_eq!(simulation.jump_to(4).unwrap().tops(), "MCD");
+        assert!(simulation.forward().is_none());
+        assert!(simulation.jump_to(5).is_none());
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+/// Why crates couldn't be moved, each variant holds the faulty stack number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// The stack doesn't have enough crates left
+    EmptyStack(usize),
+    UnknownStack(usize),
+    SameSourceAndDestination(usize),
+}
+impl std::error::Error for MoveError {}
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyStack(id) => f.write_fmt(format_args!("Not enough crates on stack {id}")),
+            Self::UnknownStack(id) => f.write_fmt(format_args!("There is no stack {id}")),
+            Self::SameSourceAndDestination(id) => f.write_fmt(format_args!(
+                "Can't move crates from stack {id} onto itself"
+            )),
+        }
+    }
+}
+
+/// A crate, identified by the label drawn on it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Crate(String);
+
+impl Crate {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self(label.into())
+    }
+
+    pub fn label(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Crate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("[{}]", self.0))
+    }
+}
+
+/// The stacks of crates, indexed by their number.
+///
+/// Each stack is stored bottom first, so the top crate is the last one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Stacks(BTreeMap<usize, Vec<Crate>>);
+
+impl Stacks {
+    /// Adds an empty stack, does nothing if it already exists
+    pub fn add_stack(&mut self, id: usize) {
+        self.0.entry(id).or_default();
+    }
+
+    /// Removes a stack with its crates, returning them if it existed
+    pub fn remove_stack(&mut self, id: usize) -> Option<Vec<Crate>> {
+        self.0.remove(&id)
+    }
+
+    /// Puts a crate on top of the given stack, creating it if needed
+    pub fn push(&mut self, id: usize, crate_: Crate) {
+        self.0.entry(id).or_default().push(crate_);
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.keys().copied()
+    }
+
+    /// The crates of a stack, from the bottom to the top
+    pub fn stack(&self, id: usize) -> Option<&[Crate]> {
+        self.0.get(&id).map(Vec::as_slice)
+    }
+
+    /// The number of crates on a stack, zero if it doesn't exist
+    pub fn height(&self, id: usize) -> usize {
+        self.0.get(&id).map(Vec::len).unwrap_or_default()
+    }
+
+    pub fn top(&self, id: usize) -> Option<&Crate> {
+        self.0.get(&id).and_then(|stack| stack.last())
+    }
+
+    /// Checks that `quantity` crates can be moved from `from` to `to`
+    pub fn check_move(&self, quantity: usize, from: usize, to: usize) -> Result<(), MoveError> {
+        if from == to {
+            return Err(MoveError::SameSourceAndDestination(from));
+        }
+        let source = self.0.get(&from).ok_or(MoveError::UnknownStack(from))?;
+        if !self.0.contains_key(&to) {
+            return Err(MoveError::UnknownStack(to));
+        }
+        if source.len() < quantity {
+            return Err(MoveError::EmptyStack(from));
+        }
+        Ok(())
+    }
+
+    /// Moves the top crate of `from` on top of `to`
+    pub fn move_one(&mut self, from: usize, to: usize) -> Result<(), MoveError> {
+        self.move_many(1, from, to)
+    }
+
+    /// Moves the `quantity` top crates of `from` on top of `to` at once,
+    /// keeping their order. The stacks are left untouched on failure.
+    pub fn move_many(&mut self, quantity: usize, from: usize, to: usize) -> Result<(), MoveError> {
+        self.check_move(quantity, from, to)?;
+        let source = self.0.get_mut(&from).unwrap();
+        let crates = source.split_off(source.len() - quantity);
+        self.0.get_mut(&to).unwrap().extend(crates);
+        Ok(())
+    }
+
+    /// The labels of the top crate of each stack, empty stacks are skipped
+    pub fn tops(&self) -> String {
+        self.ids()
+            .filter_map(|id| self.top(id))
+            .map(Crate::label)
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Stacks {
+    /// Renders the stacks back to the puzzle drawing, each column being as
+    /// wide as its widest crate or stack number
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let widths = self
+            .0
+            .iter()
+            .map(|(id, stack)| {
+                stack
+                    .iter()
+                    .map(|crate_| crate_.label().chars().count())
+                    .chain(std::iter::once(id.to_string().len()))
+                    .max()
+                    .unwrap()
+                    + 2
+            })
+            .collect::<Vec<_>>();
+        let height = self.0.values().map(Vec::len).max().unwrap_or_default();
+        for level in (0..height).rev() {
+            let row = self
+                .0
+                .values()
+                .zip(&widths)
+                .map(|(stack, width)| match stack.get(level) {
+                    Some(crate_) => format!("{:^width$}", crate_.to_string()),
+                    None => " ".repeat(*width),
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        let footer = self
+            .0
+            .keys()
+            .zip(&widths)
+            .map(|(id, width)| format!("{id:^width$}"))
+            .collect::<Vec<_>>();
+        write!(f, "{}", footer.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crate, MoveError, Stacks};
+
+    fn sample() -> Stacks {
+        let mut stacks = Stacks::default();
+        for (id, labels) in [(1, "ZN"), (2, "MCD"), (3, "P")] {
+            for label in labels.chars() {
+                stacks.push(id, Crate::new(label.to_string()));
+            }
+        }
+        stacks
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            sample().to_string(),
+            "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 "
+        );
+    }
+
+    #[test]
+    fn moves() {
+        let mut stacks = sample();
+        assert_eq!(stacks.tops(), "NDP");
+        stacks.move_one(2, 1).unwrap();
+        assert_eq!(stacks.top(1), Some(&Crate::new("D")));
+        stacks.move_many(3, 1, 3).unwrap();
+        assert_eq!(stacks.tops(), "CD");
+        stacks.move_one(3, 1).unwrap();
+        assert_eq!(stacks.tops(), "DCN");
+    }
+
+    #[test]
+    fn invalid_moves() {
+        let mut stacks = sample();
+        assert_eq!(stacks.move_many(4, 2, 1), Err(MoveError::EmptyStack(2)));
+        assert_eq!(stacks.move_one(4, 1), Err(MoveError::UnknownStack(4)));
+        assert_eq!(stacks.move_one(1, 4), Err(MoveError::UnknownStack(4)));
+        assert_eq!(
+            stacks.move_one(1, 1),
+            Err(MoveError::SameSourceAndDestination(1))
+        );
+        assert_eq!(stacks, sample());
+    }
+}
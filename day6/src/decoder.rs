@@ -0,0 +1,131 @@
+use std::ops::Range;
+
+use crate::stream::{MarkerDetector, MarkerKind};
+
+/// A marker and the payload following it, up to the next marker of the same
+/// kind or the end of the datastream. Offsets are in bytes from the start of
+/// the datastream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub kind: MarkerKind,
+    pub marker: Range<usize>,
+    pub payload: Range<usize>,
+}
+
+impl Frame {
+    pub fn payload<'a>(&self, datastream: &'a [u8]) -> &'a [u8] {
+        &datastream[self.payload.clone()]
+    }
+}
+
+/// Splits a datastream into frames of one kind.
+///
+/// Markers of the same kind never overlap: the search for the next marker
+/// starts over right after the previous one. Anything before the first
+/// marker isn't part of any frame.
+pub struct Frames<'a> {
+    datastream: &'a [u8],
+    kind: MarkerKind,
+    /// Where the search for the next marker stopped
+    position: usize,
+    /// The marker of the frame being decoded
+    marker: Option<Range<usize>>,
+}
+
+impl<'a> Frames<'a> {
+    pub fn new(datastream: &'a [u8], kind: MarkerKind) -> Self {
+        let mut frames = Self {
+            datastream,
+            kind,
+            position: 0,
+            marker: None,
+        };
+        frames.marker = frames.next_marker();
+        frames
+    }
+
+    fn next_marker(&mut self) -> Option<Range<usize>> {
+        let mut detector = MarkerDetector::new(self.kind.window()).unwrap();
+        while self.position < self.datastream.len() {
+            let byte = self.datastream[self.position];
+            self.position += 1;
+            if detector.push(byte) {
+                return Some(self.position - self.kind.window()..self.position);
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let marker = self.marker.take()?;
+        self.marker = self.next_marker();
+        let payload_end = self
+            .marker
+            .as_ref()
+            .map_or(self.datastream.len(), |next| next.start);
+        Some(Frame {
+            kind: self.kind,
+            payload: marker.end..payload_end,
+            marker,
+        })
+    }
+}
+
+/// Decodes the packets and the messages of a datastream, ordered by where
+/// their marker starts. Trailing line breaks are not part of the datastream.
+pub fn decode(datastream: &str) -> Vec<Frame> {
+    let datastream = datastream.trim_end_matches(['\n', '\r']).as_bytes();
+    let mut frames = Frames::new(datastream, MarkerKind::StartOfPacket)
+        .chain(Frames::new(datastream, MarkerKind::StartOfMessage))
+        .collect::<Vec<_>>();
+    frames.sort_by_key(|frame| frame.marker.start);
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, Frame, Frames};
+    use crate::stream::MarkerKind;
+
+    #[test]
+    fn packets() {
+        let datastream = b"aaabcdxxxefghyyzz";
+        let frames = Frames::new(datastream, MarkerKind::StartOfPacket).collect::<Vec<_>>();
+        assert_eq!(
+            frames,
+            [
+                Frame {
+                    kind: MarkerKind::StartOfPacket,
+                    marker: 2..6,
+                    payload: 6..8,
+                },
+                Frame {
+                    kind: MarkerKind::StartOfPacket,
+                    marker: 8..12,
+                    payload: 12..17,
+                },
+            ]
+        );
+        assert_eq!(frames[1].payload(datastream), b"hyyzz");
+    }
+
+    #[test]
+    fn messages() {
+        let frames = decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb\n");
+        let messages = frames
+            .iter()
+            .filter(|frame| frame.kind == MarkerKind::StartOfMessage)
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].marker.end, 19);
+        assert_eq!(messages[0].payload, 19..30);
+        assert_eq!(frames[0].marker, 3..7);
+        assert!(frames
+            .windows(2)
+            .all(|w| w[0].marker.start <= w[1].marker.start));
+    }
+}
@@ -1,4 +1,22 @@
-use std::collections::HashSet;
+mod decoder;
+mod stream;
+
+use std::{collections::HashSet, io::Read, time::Instant};
+use stream::{MarkerDetector, MarkerKind, Markers};
+
+/// Finds the end of the first `window` bytes that are all different.
+///
+/// Slides over the input once, see [`MarkerDetector`]. Trailing line breaks
+/// are not part of the datastream. Returns `None` if there's no such marker,
+/// which is always the case for an empty window.
+fn find_marker(input: &str, window: usize) -> Option<usize> {
+    let mut detector = MarkerDetector::new(window)?;
+    input
+        .trim_end_matches(['\n', '\r'])
+        .bytes()
+        .position(|byte| detector.push(byte))
+        .map(|index| index + 1)
+}
 
 fn first_marker(input: &str) -> usize {
     let chars = input.chars().collect::<Vec<_>>();
@@ -29,17 +47,125 @@ fn second_marker(input: &str) -> usize {
     i
 }
 
-fn main() {
-    let buff = std::fs::read_to_string("./src/input.txt").unwrap();
-    let marker_1 = first_marker(&buff);
-    println!("{}", marker_1);
-    let marker_2 = second_marker(&buff);
-    println!("{}", marker_2);
+/// Times the sliding window against the original implementations
+fn bench(input: &str) {
+    const ITERATIONS: u32 = 100;
+    let time = |name: &str, marker: &dyn Fn() -> Option<usize>| {
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(marker());
+        }
+        println!("{name}: {:?} per run", start.elapsed() / ITERATIONS);
+    };
+    time("first_marker", &|| Some(first_marker(input)));
+    time("find_marker(4)", &|| find_marker(input, 4));
+    time("second_marker", &|| Some(second_marker(input)));
+    time("find_marker(14)", &|| find_marker(input, 14));
+}
+
+/// Prints the markers of the datastream as they are found, either only the
+/// first of each kind or all of them
+fn stream_markers(reader: impl Read, all: bool) -> std::io::Result<()> {
+    let mut first_found = Vec::default();
+    for marker in Markers::new(reader) {
+        let marker = marker?;
+        if all {
+            println!("{} marker at {}", marker.kind, marker.position);
+        } else if !first_found.contains(&marker.kind) {
+            let part = match marker.kind {
+                MarkerKind::StartOfPacket => 1,
+                MarkerKind::StartOfMessage => 2,
+            };
+            println!("Part {part}: {}", marker.position);
+            first_found.push(marker.kind);
+            if first_found.len() == 2 {
+                return Ok(());
+            }
+        }
+    }
+    for kind in [MarkerKind::StartOfPacket, MarkerKind::StartOfMessage] {
+        if !all && !first_found.contains(&kind) {
+            println!("No {kind} marker found");
+        }
+    }
+    Ok(())
+}
+
+/// Prints every packet and message of the datastream
+fn print_frames(datastream: &str) {
+    let frames = decoder::decode(datastream);
+    for frame in frames.iter() {
+        let payload = String::from_utf8_lossy(frame.payload(datastream.as_bytes()));
+        let preview = payload.chars().take(20).collect::<String>();
+        println!(
+            "{} marker at {}..{}, payload at {}..{}: {preview}{}",
+            frame.kind,
+            frame.marker.start,
+            frame.marker.end,
+            frame.payload.start,
+            frame.payload.end,
+            if preview.len() < payload.len() {
+                "…"
+            } else {
+                ""
+            }
+        );
+    }
+    for kind in [MarkerKind::StartOfPacket, MarkerKind::StartOfMessage] {
+        let count = frames.iter().filter(|frame| frame.kind == kind).count();
+        println!("{count} {kind} frames");
+    }
+}
+
+/// Usage: `day6 [--all] [--frames] [--bench] [input|-]`, `-` reads the
+/// datastream from stdin
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let all = args.iter().any(|arg| arg == "--all");
+    let input = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .unwrap_or("./src/input.txt");
+    if args.iter().any(|arg| arg == "--bench") {
+        bench(&std::fs::read_to_string(input)?);
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--frames") {
+        let mut datastream = String::default();
+        if input == "-" {
+            std::io::stdin().read_to_string(&mut datastream)?;
+        } else {
+            datastream = std::fs::read_to_string(input)?;
+        }
+        print_frames(&datastream);
+        return Ok(());
+    }
+    if input == "-" {
+        stream_markers(std::io::stdin().lock(), all)?;
+    } else {
+        stream_markers(std::fs::File::open(input)?, all)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{first_marker, second_marker};
+    use crate::{
+        decoder::{decode, Frames},
+        find_marker, first_marker, second_marker,
+        stream::{MarkerKind, Markers},
+    };
+    use aoc::{fuzz, gen, rng::Rng};
+    use std::collections::HashSet;
+
+    const SAMPLES: [&str; 5] = [
+        "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+        "bvwbjplbgvbhsrlpgdmjqwftvncz",
+        "nppdvjthqldpwncqszvftbrmjlhg",
+        "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+        "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+    ];
 
     #[test]
     fn test_first_marker() {
@@ -58,4 +184,121 @@ mod tests {
         assert_eq!(second_marker("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
         assert_eq!(second_marker("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
     }
+
+    #[test]
+    fn test_find_marker() {
+        for sample in SAMPLES {
+            assert_eq!(find_marker(sample, 4), Some(first_marker(sample)));
+            assert_eq!(find_marker(sample, 14), Some(second_marker(sample)));
+        }
+        assert_eq!(find_marker("abcdef", 6), Some(6));
+        assert_eq!(find_marker("aab", 1), Some(1));
+    }
+
+    #[test]
+    fn test_no_marker() {
+        assert_eq!(find_marker("", 4), None);
+        assert_eq!(find_marker("abc", 4), None);
+        assert_eq!(find_marker("abcabcabc", 4), None);
+        assert_eq!(find_marker("abcd", 0), None);
+        // The line break ending the file isn't a character of the datastream
+        assert_eq!(find_marker("abc\n", 4), None);
+        assert_eq!(find_marker("aabc\r\n", 3), Some(4));
+        assert_eq!(find_marker("abcd\n", 4), Some(4));
+    }
+
+    #[test]
+    fn line_breaks_inside() {
+        // Only the line breaks ending the input are left out, the others are
+        // bytes of the datastream like any other
+        for input in [
+            "ab\ncd\n",
+            "aab\nc\r\n",
+            "abc\n\nabcd\r\n\n",
+            "a\nb\rcdefghijklmnop\n",
+        ] {
+            let markers = Markers::new(input.as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            for kind in [MarkerKind::StartOfPacket, MarkerKind::StartOfMessage] {
+                let first = markers.iter().find(|marker| marker.kind == kind);
+                assert_eq!(
+                    first.map(|marker| marker.position),
+                    find_marker(input, kind.window()),
+                    "{input:?}"
+                );
+                let frames = decode(input);
+                let frame = frames.iter().find(|frame| frame.kind == kind);
+                assert_eq!(
+                    frame.map(|frame| frame.marker.end),
+                    find_marker(input, kind.window()),
+                    "{input:?}"
+                );
+            }
+        }
+        assert_eq!(find_marker("ab\ncd\n", 4), Some(4));
+    }
+
+    #[test]
+    fn matches_naive_markers() {
+        let mut rng = Rng::new(6);
+        let naive_markers = [
+            (MarkerKind::StartOfPacket, first_marker as fn(&str) -> usize),
+            (MarkerKind::StartOfMessage, second_marker),
+        ];
+        for _ in 0..2000 {
+            // Few different letters make markers rare and late
+            let letters = rng.between(2, 16);
+            let length = rng.between(0, 200);
+            let input = (0..length)
+                .map(|_| (b'a' + rng.below(letters) as u8) as char)
+                .collect::<String>();
+            let markers = Markers::new(input.as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            for (kind, naive_marker) in naive_markers {
+                let window = kind.window();
+                let found = find_marker(&input, window);
+                let first = markers.iter().find(|marker| marker.kind == kind);
+                assert_eq!(found, first.map(|marker| marker.position), "{input}");
+                match found {
+                    Some(end) => assert_eq!(end, naive_marker(&input), "{input}"),
+                    // The original solution stops at the end without a marker
+                    None => assert_eq!(naive_marker(&input), length.max(window - 1)),
+                }
+                let frames = Frames::new(input.as_bytes(), kind).collect::<Vec<_>>();
+                assert_eq!(frames.first().map(|frame| frame.marker.end), found);
+                for frame in frames {
+                    let marker = &input.as_bytes()[frame.marker];
+                    assert_eq!(marker.iter().collect::<HashSet<_>>().len(), window);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn planted_markers() {
+        for seed in 0..100 {
+            let (stream, [packet, message]) = gen::datastream(&mut Rng::new(seed), 1000);
+            assert_eq!(find_marker(&stream, 4), Some(packet));
+            assert_eq!(find_marker(&stream, 14), Some(message));
+            assert_eq!(first_marker(&stream), packet);
+            assert_eq!(second_marker(&stream), message);
+        }
+    }
+
+    #[test]
+    fn fuzz_datastreams() {
+        fuzz::check(&SAMPLES, |input| {
+            for window in [0, 4, 14] {
+                find_marker(input, window);
+            }
+            for frame in decode(input) {
+                frame.payload(input.as_bytes());
+            }
+            for marker in Markers::new(input.as_bytes()) {
+                marker.unwrap();
+            }
+        });
+    }
 }
@@ -0,0 +1,211 @@
+use std::{collections::VecDeque, io::Read};
+
+/// Size of the chunks read from the datastream
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Tells whether the last `window` bytes pushed are all different, only
+/// keeping those bytes around.
+#[derive(Debug, Clone)]
+pub struct MarkerDetector {
+    /// Ring buffer of the last bytes, `next` being where the next one goes
+    last_bytes: Vec<u8>,
+    next: usize,
+    /// How many bytes were pushed, up to the window size
+    filled: usize,
+    counts: [u32; 256],
+    /// How many byte values appear more than once in the window
+    duplicates: usize,
+}
+
+impl MarkerDetector {
+    /// Returns `None` for an empty window as it can't contain a marker
+    pub fn new(window: usize) -> Option<Self> {
+        if window == 0 {
+            return None;
+        }
+        Some(Self {
+            last_bytes: vec![0; window],
+            next: 0,
+            filled: 0,
+            counts: [0; 256],
+            duplicates: 0,
+        })
+    }
+
+    /// Adds the next byte, returns whether it ends a marker
+    pub fn push(&mut self, byte: u8) -> bool {
+        let window = self.last_bytes.len();
+        if self.filled == window {
+            let old = self.last_bytes[self.next] as usize;
+            if self.counts[old] == 2 {
+                self.duplicates -= 1;
+            }
+            self.counts[old] -= 1;
+        } else {
+            self.filled += 1;
+        }
+        self.last_bytes[self.next] = byte;
+        self.next = (self.next + 1) % window;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicates += 1;
+        }
+        self.filled == window && self.duplicates == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+impl MarkerKind {
+    /// How many different characters make the marker
+    pub fn window(&self) -> usize {
+        match self {
+            Self::StartOfPacket => 4,
+            Self::StartOfMessage => 14,
+        }
+    }
+}
+
+impl std::fmt::Display for MarkerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StartOfPacket => f.write_str("start-of-packet"),
+            Self::StartOfMessage => f.write_str("start-of-message"),
+        }
+    }
+}
+
+/// A marker ending after `position` bytes of the datastream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub kind: MarkerKind,
+    pub position: usize,
+}
+
+/// Iterates over every marker of a datastream as it's read.
+///
+/// The datastream is read in chunks, so only a chunk and the last characters
+/// of each marker kind are kept in memory. Every byte is part of the
+/// datastream except the line breaks ending it, so positions are byte offsets
+/// in the input, as with [`find_marker`](crate::find_marker).
+pub struct Markers<R> {
+    reader: R,
+    chunk: Vec<u8>,
+    /// The part of the chunk that was read but not processed yet
+    unprocessed: std::ops::Range<usize>,
+    detectors: Vec<(MarkerKind, MarkerDetector)>,
+    /// How many bytes of the datastream were processed
+    position: usize,
+    /// Markers found at the current position but not yielded yet
+    found: VecDeque<Marker>,
+    /// Markers ending on a line break, only yielded once something other
+    /// than a line break follows, as trailing line breaks aren't part of the
+    /// datastream
+    pending: Vec<Marker>,
+}
+
+impl<R: Read> Markers<R> {
+    pub fn new(reader: R) -> Self {
+        let kinds = [MarkerKind::StartOfPacket, MarkerKind::StartOfMessage];
+        Self {
+            reader,
+            chunk: vec![0; CHUNK_SIZE],
+            unprocessed: 0..0,
+            detectors: kinds
+                .into_iter()
+                .map(|kind| (kind, MarkerDetector::new(kind.window()).unwrap()))
+                .collect(),
+            position: 0,
+            found: VecDeque::default(),
+            pending: Vec::default(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = std::io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(marker) = self.found.pop_front() {
+                return Some(Ok(marker));
+            }
+            if self.unprocessed.is_empty() {
+                match self.reader.read(&mut self.chunk) {
+                    Ok(0) => return None,
+                    Ok(read) => self.unprocessed = 0..read,
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            let byte = self.chunk[self.unprocessed.start];
+            self.unprocessed.start += 1;
+            let line_break = byte == b'\n' || byte == b'\r';
+            if !line_break {
+                self.found.extend(self.pending.drain(..));
+            }
+            self.position += 1;
+            for (kind, detector) in self.detectors.iter_mut() {
+                if detector.push(byte) {
+                    let marker = Marker {
+                        kind: *kind,
+                        position: self.position,
+                    };
+                    if line_break {
+                        self.pending.push(marker);
+                    } else {
+                        self.found.push_back(marker);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Marker, MarkerKind, Markers};
+
+    /// Never returns more than a few bytes at once
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.0.len().min(buf.len()).min(3);
+            buf[..read].copy_from_slice(&self.0[..read]);
+            self.0 = &self.0[read..];
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn every_marker() {
+        let markers = Markers::new(Trickle(b"aabcdd\nabcdefghijklmn\n"))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let positions = |kind| {
+            markers
+                .iter()
+                .filter(|marker| marker.kind == kind)
+                .map(|marker| marker.position)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            positions(MarkerKind::StartOfPacket),
+            [5, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21]
+        );
+        // The line break in the middle is part of the datastream
+        assert_eq!(positions(MarkerKind::StartOfMessage), [20, 21]);
+        assert_eq!(
+            markers.first(),
+            Some(&Marker {
+                kind: MarkerKind::StartOfPacket,
+                position: 5
+            })
+        );
+    }
+}
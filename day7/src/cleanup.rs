@@ -0,0 +1,316 @@
+use crate::tree::{FsTree, NodeId};
+
+/// Above that many bits, 256MiB, looking for the smallest set of directories
+/// to delete would need too much memory
+const MAX_EXACT_BITS: u128 = 1 << 31;
+
+/// The space an update needs on a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cleanup {
+    pub capacity: u128,
+    pub required: u128,
+}
+
+/// Directories to delete together, none of them inside another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deletion {
+    pub dirs: Vec<NodeId>,
+    /// Whether no other directories free enough space while deleting less.
+    /// Only the smallest directory is looked for when the filesystem is too
+    /// large for an exact search.
+    pub exact: bool,
+}
+
+impl Default for Cleanup {
+    fn default() -> Self {
+        Self {
+            capacity: 70000000,
+            required: 30000000,
+        }
+    }
+}
+
+impl Cleanup {
+    /// How much space must be freed, `None` if there is already enough.
+    ///
+    /// Needing more than can be counted, when more is required than the disk
+    /// holds, is [`u128::MAX`], which no deletion can free.
+    pub fn needed(&self, tree: &FsTree) -> Option<u128> {
+        let used = tree.node(FsTree::ROOT).size();
+        match self.capacity.checked_sub(self.required) {
+            Some(available) => used.checked_sub(available).filter(|needed| *needed > 0),
+            None => Some(used.saturating_add(self.required - self.capacity)),
+        }
+    }
+
+    /// The smallest directory freeing enough space once deleted, `None` if
+    /// no deletion is needed or none is enough
+    pub fn smallest_directory(&self, tree: &FsTree) -> Option<NodeId> {
+        let needed = self.needed(tree)?;
+        tree.dirs()
+            .filter(|id| tree.node(*id).size() >= needed)
+            .min_by_key(|id| tree.node(*id).size())
+    }
+
+    /// The directories to delete to free enough space while deleting as
+    /// little as possible, `None` if no deletion is needed or none is enough.
+    ///
+    /// Deleting a directory deletes the ones inside, so none of the returned
+    /// directories is inside another. For large filesystems only the
+    /// [smallest directory](Self::smallest_directory) is looked for, and the
+    /// deletion isn't [exact](Deletion::exact).
+    pub fn smallest_set(&self, tree: &FsTree) -> Option<Deletion> {
+        let needed = self.needed(tree)?;
+        let single = self.smallest_directory(tree)?;
+        // No set of directories is worth deleting more than the best single one
+        let upper = tree.node(single).size();
+        // Directories in depth first order, with where their subtree ends
+        let walk = tree
+            .walk(FsTree::ROOT)
+            .filter(|(id, _)| tree.node(*id).is_dir())
+            .collect::<Vec<_>>();
+        if (upper + 1) * (walk.len() as u128 + 1) > MAX_EXACT_BITS {
+            return Some(Deletion {
+                dirs: vec![single],
+                exact: false,
+            });
+        }
+        let subtree_end = (0..walk.len())
+            .map(|i| {
+                (i + 1..walk.len())
+                    .find(|j| walk[*j].1 <= walk[i].1)
+                    .unwrap_or(walk.len())
+            })
+            .collect::<Vec<_>>();
+        // reachable[i] holds the total sizes, up to `upper`, reachable by
+        // deleting directories among walk[i..] without nesting
+        let width = upper as usize + 1;
+        // Larger directories are never part of the set, whatever their size
+        let sizes = walk
+            .iter()
+            .map(|(id, _)| {
+                usize::try_from(tree.node(*id).size())
+                    .ok()
+                    .filter(|size| *size < width)
+            })
+            .collect::<Vec<_>>();
+
+        let mut reachable = vec![Bits::new(width); walk.len() + 1];
+        reachable[walk.len()].set(0);
+        for i in (0..walk.len()).rev() {
+            let mut bits = reachable[i + 1].clone();
+            if let Some(size) = sizes[i] {
+                bits.or_shifted(&reachable[subtree_end[i]], size);
+            }
+            reachable[i] = bits;
+        }
+
+        let mut total = (needed as usize..width).find(|total| reachable[0].get(*total))?;
+        let mut set = Vec::default();
+        let mut i = 0;
+        while total > 0 {
+            if reachable[i + 1].get(total) {
+                i += 1;
+            } else {
+                set.push(walk[i].0);
+                total -= sizes[i].expect("Only directories small enough are taken");
+                i = subtree_end[i];
+            }
+        }
+        Some(Deletion {
+            dirs: set,
+            exact: true,
+        })
+    }
+}
+
+/// A fixed size set of bits
+#[derive(Debug, Clone)]
+struct Bits {
+    words: Vec<u64>,
+    width: usize,
+}
+
+impl Bits {
+    fn new(width: usize) -> Self {
+        Self {
+            words: vec![0; width.div_ceil(64)],
+            width,
+        }
+    }
+
+    fn get(&self, bit: usize) -> bool {
+        self.words[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    fn set(&mut self, bit: usize) {
+        self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    /// Adds the bits of `other` moved up by `shift`, dropping the overflow
+    fn or_shifted(&mut self, other: &Bits, shift: usize) {
+        let (word_shift, bit_shift) = (shift / 64, shift % 64);
+        for k in (word_shift..self.words.len()).rev() {
+            let mut word = other.words[k - word_shift] << bit_shift;
+            if bit_shift > 0 && k > word_shift {
+                word |= other.words[k - word_shift - 1] >> (64 - bit_shift);
+            }
+            self.words[k] |= word;
+        }
+        let extra = self.words.len() * 64 - self.width;
+        if extra > 0 {
+            *self.words.last_mut().unwrap() &= u64::MAX >> extra;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cleanup;
+    use crate::{read_input, tree::FsTree};
+    use aoc::{gen, rng::Rng};
+    use std::path::PathBuf;
+
+    #[test]
+    fn already_enough_space() {
+        let tree = read_input(include_str!("sample_input.txt"), false).unwrap();
+        let cleanup = Cleanup {
+            capacity: 70000000,
+            required: 1000,
+        };
+        assert_eq!(cleanup.needed(&tree), None);
+        assert_eq!(cleanup.smallest_directory(&tree), None);
+        assert_eq!(cleanup.smallest_set(&tree), None);
+    }
+
+    #[test]
+    fn smallest_set() {
+        let tree = read_input(
+            "$ cd /\n$ ls\ndir x\ndir y\ndir z\n$ cd x\n$ ls\n5 a\n$ cd ../y\n$ ls\n6 b\ndir w\n$ cd w\n$ ls\n3 c\n$ cd /z\n$ ls\n12 d\n",
+            false,
+        )
+        .unwrap();
+        let cleanup = Cleanup {
+            capacity: 35,
+            required: 17,
+        };
+        assert_eq!(cleanup.needed(&tree), Some(8));
+        let single = cleanup.smallest_directory(&tree).unwrap();
+        assert_eq!(tree.path_of(single), PathBuf::from("/y"));
+        let deletion = cleanup.smallest_set(&tree).unwrap();
+        assert!(deletion.exact);
+        let mut set = deletion
+            .dirs
+            .into_iter()
+            .map(|id| tree.path_of(id))
+            .collect::<Vec<_>>();
+        set.sort();
+        assert_eq!(set, [PathBuf::from("/x"), PathBuf::from("/y/w")]);
+        // Nothing can free more than everything
+        let cleanup = Cleanup {
+            capacity: 10,
+            required: 100,
+        };
+        assert_eq!(cleanup.smallest_set(&tree), None);
+        assert_eq!(tree.node(FsTree::ROOT).size(), 26);
+    }
+
+    #[test]
+    fn extreme_sizes() {
+        let tree = read_input(
+            "$ cd /\n$ ls\ndir x\ndir y\ndir z\n$ cd x\n$ ls\n18446744073709551615 f\n6 g\n\
+             $ cd ../y\n$ ls\n10 h\n$ cd ../z\n$ ls\n3 i\n",
+            false,
+        )
+        .unwrap();
+        // /x is larger than any usize, it must not wrap around to a small size
+        let cleanup = Cleanup {
+            capacity: 18446744073709551634,
+            required: 8,
+        };
+        assert_eq!(cleanup.needed(&tree), Some(8));
+        let deletion = cleanup.smallest_set(&tree).unwrap();
+        assert!(deletion.exact);
+        assert_eq!(tree.path_of(deletion.dirs[0]), PathBuf::from("/y"));
+        assert_eq!(deletion.dirs.len(), 1);
+
+        // Requiring more than can be counted can't overflow
+        let cleanup = Cleanup {
+            capacity: 70000000,
+            required: u128::MAX,
+        };
+        assert_eq!(cleanup.needed(&tree), Some(u128::MAX));
+        assert_eq!(cleanup.smallest_directory(&tree), None);
+        assert_eq!(cleanup.smallest_set(&tree), None);
+        let cleanup = Cleanup {
+            capacity: u128::MAX,
+            required: u128::MAX,
+        };
+        assert_eq!(cleanup.needed(&tree), Some(18446744073709551634));
+        let cleanup = Cleanup {
+            capacity: u128::MAX,
+            required: 0,
+        };
+        assert_eq!(cleanup.needed(&tree), None);
+    }
+
+    #[test]
+    fn too_large_for_exact_set() {
+        let tree = read_input(
+            "$ cd /\n$ ls\ndir x\n1 b\n$ cd x\n$ ls\n5000000000 a\n",
+            false,
+        )
+        .unwrap();
+        let cleanup = Cleanup {
+            capacity: 6000000000,
+            required: 2000000000,
+        };
+        let deletion = cleanup.smallest_set(&tree).unwrap();
+        assert!(!deletion.exact);
+        assert_eq!(deletion.dirs, [cleanup.smallest_directory(&tree).unwrap()]);
+        assert_eq!(tree.path_of(deletion.dirs[0]), PathBuf::from("/x"));
+    }
+
+    #[test]
+    fn smallest_set_matches_brute_force() {
+        let mut rng = Rng::new(40);
+        for seed in 0..200 {
+            let session = gen::terminal_session(&mut Rng::new(seed), 12);
+            let tree = read_input(&session, true).unwrap();
+            let dirs = tree.dirs().collect::<Vec<_>>();
+            let paths = dirs.iter().map(|id| tree.path_of(*id)).collect::<Vec<_>>();
+            let used = tree.node(FsTree::ROOT).size() as usize;
+            let cleanup = Cleanup {
+                capacity: used as u128 + 10,
+                required: rng.between(11, used + 10) as u128,
+            };
+            let needed = cleanup.needed(&tree).unwrap();
+            // Every set of directories where none is inside another
+            let best = (0..1usize << dirs.len())
+                .filter(|set| {
+                    let chosen = (0..dirs.len()).filter(|i| set & 1 << i != 0);
+                    chosen.clone().all(|i| {
+                        chosen
+                            .clone()
+                            .all(|j| i == j || !paths[i].starts_with(&paths[j]))
+                    })
+                })
+                .map(|set| {
+                    (0..dirs.len())
+                        .filter(|i| set & 1 << i != 0)
+                        .map(|i| tree.node(dirs[i]).size())
+                        .sum::<u128>()
+                })
+                .filter(|total| *total >= needed)
+                .min();
+            let deletion = cleanup.smallest_set(&tree).unwrap();
+            assert!(deletion.exact);
+            let total = deletion
+                .dirs
+                .iter()
+                .map(|id| tree.node(*id).size())
+                .sum::<u128>();
+            assert_eq!(Some(total), best, "{session}");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    fmt::Write,
+    fs::{self, File},
+    io,
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::tree::{FsTree, NodeId};
+
+/// Creates a new empty directory under the system temp directory
+pub fn temp_root(prefix: &str) -> io::Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+    for attempt in 0..100 {
+        let path =
+            std::env::temp_dir().join(format!("{prefix}-{}-{nanos}-{attempt}", std::process::id()));
+        match fs::create_dir(&path) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "Couldn't find a free temp directory",
+    ))
+}
+
+/// Recreates the filesystem inside `root`. Files are sparse: they have the
+/// recorded size but take next to no space on disk.
+pub fn materialize(tree: &FsTree, root: &Path) -> io::Result<()> {
+    fs::create_dir_all(root)?;
+    for (id, _) in tree.walk(FsTree::ROOT).skip(1) {
+        let path = root.join(relative_path(tree, id)?);
+        let node = tree.node(id);
+        if node.is_dir() {
+            fs::create_dir(&path)?;
+        } else {
+            let size = u64::try_from(node.size())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "File too large"))?;
+            File::create(&path)?.set_len(size)?;
+        }
+    }
+    Ok(())
+}
+
+/// The path of a node relative to the root, making sure a name can't escape
+/// its directory
+fn relative_path(tree: &FsTree, id: NodeId) -> io::Result<PathBuf> {
+    let path = tree.path_of(id);
+    let relative = path.strip_prefix("/").unwrap_or(&path);
+    let name = tree.node(id).name();
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(relative.to_owned()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid file name: {name}"),
+        )),
+    }
+}
+
+/// Explores a directory on disk and writes the terminal session that would
+/// list it, entries sorted by name. Symbolic links are not followed.
+pub fn transcript(root: &Path) -> io::Result<String> {
+    fn explore(directory: &Path, output: &mut String) -> io::Result<()> {
+        let mut entries = fs::read_dir(directory)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        let mut dirs = Vec::default();
+        output.push_str("$ ls\n");
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                let _ = writeln!(output, "dir {name}");
+                dirs.push((name, entry.path()));
+            } else if file_type.is_file() {
+                let _ = writeln!(output, "{} {name}", entry.metadata()?.len());
+            }
+        }
+        for (name, path) in dirs {
+            let _ = writeln!(output, "$ cd {name}");
+            explore(&path, output)?;
+            output.push_str("$ cd ..\n");
+        }
+        Ok(())
+    }
+
+    let mut output = "$ cd /\n".to_owned();
+    explore(root, &mut output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{materialize, temp_root, transcript};
+    use crate::{read_input, report::disk_usage, tree::FsTree};
+
+    #[test]
+    fn round_trip() {
+        let tree = read_input(include_str!("sample_input.txt"), false).unwrap();
+        let root = temp_root("day7-test").unwrap();
+        materialize(&tree, &root).unwrap();
+        assert_eq!(std::fs::metadata(root.join("d/k")).unwrap().len(), 7214296);
+        let session = transcript(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(session.starts_with("$ cd /\n$ ls\ndir a\n14848514 b.txt\n"));
+        let copy = read_input(&session, false).unwrap();
+        assert_eq!(
+            disk_usage(&copy, FsTree::ROOT, false),
+            disk_usage(&tree, FsTree::ROOT, false)
+        );
+        assert_eq!(copy.size_of("/"), tree.size_of("/"));
+        // Every file and directory made it through
+        assert_eq!(
+            copy.walk(FsTree::ROOT).count(),
+            tree.walk(FsTree::ROOT).count()
+        );
+    }
+
+    #[test]
+    fn invalid_names() {
+        // Transcripts reject such names, trees built by hand don't
+        let mut tree = FsTree::default();
+        tree.add_dir(FsTree::ROOT, "..");
+        let root = temp_root("day7-test").unwrap();
+        let result = materialize(&tree, &root);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use aoc::json::Value;
+
+use crate::tree::{FsTree, NodeId};
+
+/// Quotes a string for JSON, with the escaping of the answer store
+fn json_string(s: &str) -> String {
+    Value::from(s).to_string()
+}
+
+/// Serializes the filesystem below `id` as nested JSON objects. `size` is
+/// what a node takes by itself, nothing for a directory, and
+/// `cumulative_size` includes everything inside it.
+pub fn to_json(tree: &FsTree, id: NodeId) -> String {
+    let node = tree.node(id);
+    let mut json = format!("{{\"name\":{}", json_string(node.name()));
+    if node.is_dir() {
+        let _ = write!(
+            json,
+            ",\"kind\":\"dir\",\"size\":0,\"cumulative_size\":{},\"children\":[",
+            node.size()
+        );
+        let children = tree
+            .children(id)
+            .iter()
+            .map(|child| to_json(tree, *child))
+            .collect::<Vec<_>>();
+        json.push_str(&children.join(","));
+        json.push(']');
+    } else {
+        let _ = write!(
+            json,
+            ",\"kind\":\"file\",\"size\":{0},\"cumulative_size\":{0}",
+            node.size()
+        );
+    }
+    json.push('}');
+    json
+}
+
+/// Serializes the filesystem in the format of `ncdu -o`, to be opened with
+/// `ncdu -f`. Directories are arrays starting with their own entry.
+pub fn to_ncdu(tree: &FsTree) -> String {
+    fn entry(tree: &FsTree, id: NodeId, output: &mut String) {
+        let node = tree.node(id);
+        if node.is_dir() {
+            let _ = write!(output, "[{{\"name\":{}}}", json_string(node.name()));
+            for child in tree.children(id) {
+                output.push(',');
+                entry(tree, *child, output);
+            }
+            output.push(']');
+        } else {
+            let _ = write!(
+                output,
+                "{{\"name\":{},\"asize\":{1},\"dsize\":{1}}}",
+                json_string(node.name()),
+                node.size()
+            );
+        }
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let mut output = format!(
+        "[1,0,{{\"progname\":\"day7\",\"progver\":\"{}\",\"timestamp\":{timestamp}}},",
+        env!("CARGO_PKG_VERSION")
+    );
+    entry(tree, FsTree::ROOT, &mut output);
+    output.push_str("]\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_string, to_json, to_ncdu};
+    use crate::{read_input, tree::FsTree};
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            json_string("a \"b\"\\\u{1}\t\u{1f}"),
+            "\"a \\\"b\\\"\\\\\\u0001\\t\\u001f\""
+        );
+        let tree = read_input("$ ls\ndir a\n3 b\n$ cd a\n$ ls\n4 c\n", false).unwrap();
+        assert_eq!(
+            to_json(&tree, FsTree::ROOT),
+            "{\"name\":\"/\",\"kind\":\"dir\",\"size\":0,\"cumulative_size\":7,\"children\":[\
+             {\"name\":\"a\",\"kind\":\"dir\",\"size\":0,\"cumulative_size\":4,\"children\":[\
+             {\"name\":\"c\",\"kind\":\"file\",\"size\":4,\"cumulative_size\":4}]},\
+             {\"name\":\"b\",\"kind\":\"file\",\"size\":3,\"cumulative_size\":3}]}"
+        );
+        let ncdu = to_ncdu(&tree);
+        assert!(ncdu.starts_with("[1,0,{\"progname\":\"day7\""));
+        assert!(ncdu.ends_with(
+            "[{\"name\":\"/\"},[{\"name\":\"a\"},{\"name\":\"c\",\"asize\":4,\"dsize\":4}],\
+             {\"name\":\"b\",\"asize\":3,\"dsize\":3}]]\n"
+        ));
+    }
+}
@@ -1,25 +1,65 @@
+mod cleanup;
+mod disk;
+mod export;
+mod query;
+mod report;
+mod session;
+mod shell;
+mod tree;
+
+use cleanup::Cleanup;
+use report::format_size;
+use session::{Issue, Session};
+use shell::Shell;
 use std::{
-    collections::HashMap,
-    path::{Path, PathBuf},
+    io::Write,
+    ops::RangeInclusive,
+    path::{Component, Path, PathBuf},
     str::FromStr,
 };
+use tree::FsTree;
 
 #[derive(Debug)]
 pub enum Error {
     CommandParseError(&'static str),
+    UnknownCommand(String),
     ListOutputParseError(&'static str),
+    /// A line that isn't a command while no `ls` is running
+    UnexpectedOutput(String),
+    NotADirectory(PathBuf),
+    NotFound(PathBuf),
+    /// The shell can delete anything but the root directory
+    CannotRemoveRoot,
+    Inconsistent(Issue),
+    /// Wraps an error with the line (starting at 1) of the transcript it happened at
+    Line {
+        line: usize,
+        error: Box<Error>,
+    },
 }
 impl std::error::Error for Error {}
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::CommandParseError(e) => f.write_str(e),
+            Self::UnknownCommand(command) => f.write_fmt(format_args!("Unknown command {command}")),
             Self::ListOutputParseError(e) => f.write_str(e),
+            Self::UnexpectedOutput(line) => f.write_fmt(format_args!("Output without ls: {line}")),
+            Self::NotADirectory(path) => {
+                f.write_fmt(format_args!("Not a directory: {}", path.display()))
+            }
+            Self::NotFound(path) => f.write_fmt(format_args!(
+                "No such file or directory: {}",
+                path.display()
+            )),
+            Self::CannotRemoveRoot => f.write_str("Cannot remove the root"),
+            Self::Inconsistent(issue) => issue.fmt(f),
+            Self::Line { line, error } => f.write_fmt(format_args!("Line {line}: {error}")),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     CurrentDirectory(PathBuf),
     List,
@@ -28,15 +68,17 @@ pub enum Command {
 impl FromStr for Command {
     type Err = Error;
 
+    /// `cd` without a path goes back to the root
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let command = s.trim_start_matches('$').trim();
-        if command.starts_with("ls") {
-            Ok(Self::List)
-        } else if command.starts_with("cd") {
-            let path = command.trim_start_matches("cd").trim();
-            Ok(Self::CurrentDirectory(path.into()))
-        } else {
-            Err(Error::CommandParseError("Failed to parse command"))
+        match command.split_once(char::is_whitespace) {
+            _ if command == "ls" => Ok(Self::List),
+            _ if command == "cd" => Ok(Self::CurrentDirectory("/".into())),
+            Some(("cd", path)) => Ok(Self::CurrentDirectory(path.trim().into())),
+            Some(("ls", _)) => Err(Error::CommandParseError("ls takes no arguments")),
+            _ if command.is_empty() => Err(Error::CommandParseError("Missing command")),
+            Some((name, _)) => Err(Error::UnknownCommand(name.to_owned())),
+            None => Err(Error::UnknownCommand(command.to_owned())),
         }
     }
 }
@@ -50,106 +92,318 @@ pub enum ListOutput {
 impl FromStr for ListOutput {
     type Err = Error;
 
+    /// `dir NAME` or `SIZE NAME`. Names are single entries, so they can't be
+    /// empty, `.`, `..` or contain a `/`. Sizes fit in 64 bits so that adding
+    /// them up can't overflow.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.starts_with("dir") {
-            let path = s.trim_start_matches("dir").trim();
-            Ok(Self::Directory(path.into()))
+        let (first, name) = s
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or(Error::ListOutputParseError("Missing name"))?;
+        let name = name.trim();
+        if matches!(name, "." | "..") || name.contains('/') {
+            return Err(Error::ListOutputParseError("Invalid name"));
+        }
+        if first == "dir" {
+            Ok(Self::Directory(name.into()))
         } else {
-            let mut file = s.split_whitespace();
-            let size = file.next().unwrap().parse::<u128>().unwrap();
-            let path = file.next().unwrap();
-            Ok(Self::File(size, path.into()))
+            let size = first
+                .parse::<u64>()
+                .map_err(|_| Error::ListOutputParseError("Invalid file size"))?;
+            Ok(Self::File(size.into(), name.into()))
         }
     }
 }
 
-/// Similar to fs::canonicalize but doesn't try to resolve the files
+/// Similar to fs::canonicalize but doesn't try to resolve the files,
+/// `..` at the root stays at the root like in a shell
 fn fake_canonicalize(path: impl AsRef<Path>) -> PathBuf {
     let mut new_path = PathBuf::default();
     for comp in path.as_ref().components() {
-        new_path = match comp {
-            std::path::Component::RootDir => new_path.join("/"),
-            std::path::Component::ParentDir => new_path.parent().unwrap().into(),
-            std::path::Component::Normal(p) => new_path.join(p),
-            _ => unreachable!(),
-        };
+        match comp {
+            Component::Prefix(_) | Component::RootDir => new_path = PathBuf::from("/"),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                new_path.pop();
+            }
+            Component::Normal(p) => new_path.push(p),
+        }
     }
     new_path
 }
 
-fn compute_size(tree: &HashMap<PathBuf, Vec<ListOutput>>, parent: impl AsRef<Path>) -> u128 {
-    let mut total_size = 0;
-    for v in tree.get(parent.as_ref()).unwrap() {
-        match v {
-            ListOutput::Directory(p) => {
-                let new_path = parent.as_ref().join(p);
-                total_size += compute_size(tree, new_path);
+/// Rebuilds the filesystem from the terminal session, warning about its
+/// inconsistencies or failing on the first one if `strict`
+fn read_input(buff: &str, strict: bool) -> Result<FsTree, Error> {
+    let (tree, issues) = Session::validate(buff)?;
+    for (line, issue) in issues {
+        if strict {
+            return Err(Error::Line {
+                line,
+                error: Box::new(Error::Inconsistent(issue)),
+            });
+        }
+        eprintln!("Warning: line {line}: {issue}");
+    }
+    Ok(tree)
+}
+
+/// The total size of the directories of at most `threshold`
+fn small_directories_size(tree: &FsTree, threshold: u128) -> u128 {
+    tree.dirs()
+        .map(|id| tree.node(id).size())
+        .filter(|size| *size <= threshold)
+        .sum()
+}
+
+struct Options {
+    input: Option<String>,
+    /// Print the filesystem like `tree`
+    tree: bool,
+    /// List the directories like `du`
+    du: bool,
+    /// Print the sizes like `du -h`
+    human: bool,
+    /// Print the largest directories and files
+    top: Option<usize>,
+    /// How many levels `--tree` shows
+    depth: Option<usize>,
+    /// Print the files and directories matching a glob
+    find: Option<String>,
+    /// Print the directories within a size range
+    sizes: Option<RangeInclusive<u128>>,
+    /// Print the total size of the files per extension
+    extensions: bool,
+    /// Print the filesystem as JSON
+    json: bool,
+    /// Print the filesystem in the format of `ncdu -o`
+    ncdu: bool,
+    /// Recreate the filesystem under a new temp directory
+    materialize: bool,
+    /// Fail on inconsistencies in the session instead of warning about them
+    strict: bool,
+    /// Explore the filesystem interactively
+    shell: bool,
+    /// Print the session listing this directory instead of reading a session
+    transcript: Option<PathBuf>,
+    cleanup: Cleanup,
+    /// The size under which directories count for the first puzzle
+    threshold: u128,
+}
+
+/// Parses `[--tree] [--depth N] [--du] [-h] [--top N] [--find GLOB]
+/// [--size MIN..MAX] [--extensions] [--json] [--ncdu] [--materialize]
+/// [--transcript DIR] [--strict] [--shell] [--capacity N] [--required N] [--threshold N] [input]`
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        input: None,
+        tree: false,
+        du: false,
+        human: false,
+        top: None,
+        depth: None,
+        find: None,
+        sizes: None,
+        extensions: false,
+        json: false,
+        ncdu: false,
+        materialize: false,
+        transcript: None,
+        strict: false,
+        shell: false,
+        cleanup: Cleanup::default(),
+        threshold: 100000,
+    };
+    let size = |args: &mut dyn Iterator<Item = String>, flag: &str| {
+        args.next()
+            .and_then(|size| size.parse::<u128>().ok())
+            .ok_or(format!("{flag} expects a size"))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tree" => options.tree = true,
+            "--du" => options.du = true,
+            "-h" | "--human-readable" => options.human = true,
+            "--top" => {
+                let count = args
+                    .next()
+                    .and_then(|count| count.parse::<usize>().ok())
+                    .ok_or("--top expects a number")?;
+                options.top = Some(count);
+            }
+            "--depth" => {
+                let depth = args
+                    .next()
+                    .and_then(|depth| depth.parse::<usize>().ok())
+                    .ok_or("--depth expects a number")?;
+                options.depth = Some(depth);
             }
-            ListOutput::File(size, _) => {
-                total_size += size;
+            "--find" => options.find = Some(args.next().ok_or("--find expects a glob")?),
+            "--size" => {
+                let sizes = args
+                    .next()
+                    .and_then(|sizes| query::parse_size_range(&sizes))
+                    .ok_or("--size expects MIN..MAX, MIN.. or ..MAX")?;
+                options.sizes = Some(sizes);
             }
+            "--extensions" => options.extensions = true,
+            "--json" => options.json = true,
+            "--ncdu" => options.ncdu = true,
+            "--materialize" => options.materialize = true,
+            "--transcript" => {
+                let dir = args.next().ok_or("--transcript expects a directory")?;
+                options.transcript = Some(dir.into());
+            }
+            "--strict" => options.strict = true,
+            "--shell" => options.shell = true,
+            "--capacity" => options.cleanup.capacity = size(&mut args, "--capacity")?,
+            "--required" => options.cleanup.required = size(&mut args, "--required")?,
+            "--threshold" => options.threshold = size(&mut args, "--threshold")?,
+            path => options.input = Some(path.to_owned()),
         }
     }
-    total_size
+    Ok(options)
+}
+
+/// Runs the shell on the standard input until it ends or `exit`
+fn explore(tree: FsTree, cleanup: Cleanup) -> Result<(), Box<dyn std::error::Error>> {
+    let mut shell = Shell::new(tree, cleanup);
+    let mut stdout = std::io::stdout();
+    let mut lines = std::io::stdin().lines();
+    while !shell.exited {
+        print!("{}", shell.prompt());
+        stdout.flush()?;
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+        match shell.execute(&line?) {
+            Ok(output) => print!("{output}"),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let buff = std::fs::read_to_string("./src/input.txt")?;
-    let mut current_directory = PathBuf::default();
-    let mut is_list = false;
-    let mut tree: HashMap<PathBuf, Vec<ListOutput>> = HashMap::default();
-
-    for line in buff.lines() {
-        // This is a command
-        if line.starts_with('$') {
-            match Command::from_str(line)? {
-                Command::CurrentDirectory(dir) => {
-                    current_directory = fake_canonicalize(current_directory.join(dir));
-                }
-                Command::List => {
-                    is_list = true;
-                }
+    let options = parse_args(std::env::args().skip(1))?;
+    if let Some(dir) = options.transcript {
+        print!("{}", disk::transcript(&dir)?);
+        return Ok(());
+    }
+    let buff = std::fs::read_to_string(options.input.as_deref().unwrap_or("./src/input.txt"))?;
+    let tree = read_input(&buff, options.strict)?;
+
+    if options.shell {
+        return explore(tree, options.cleanup);
+    }
+
+    if options.tree {
+        print!(
+            "{}",
+            report::tree_view(&tree, FsTree::ROOT, options.human, options.depth)
+        );
+    }
+    if options.du {
+        print!("{}", report::disk_usage(&tree, FsTree::ROOT, options.human));
+    }
+    if let Some(count) = options.top {
+        for (title, dirs) in [("directories", true), ("files", false)] {
+            println!("Largest {title}:");
+            for id in report::largest(&tree, count, dirs) {
+                let size = format_size(tree.node(id).size(), options.human);
+                println!("{size}\t{}", tree.path_of(id).display());
             }
-        } else if is_list {
-            let output = ListOutput::from_str(line)?;
-            let output_clone = output.clone();
-            tree.entry(current_directory.clone())
-                .and_modify(|entries| entries.push(output))
-                .or_insert_with(|| vec![output_clone]);
         }
     }
-
-    let mut total_size = 0;
-    for key in tree.keys() {
-        let size = compute_size(&tree, key);
-        if size <= 100000 {
-            total_size += size;
+    if let Some(pattern) = &options.find {
+        for id in query::find(&tree, pattern) {
+            println!("{}", tree.path_of(id).display());
+        }
+    }
+    if let Some(sizes) = &options.sizes {
+        for id in query::dirs_in_range(&tree, sizes) {
+            let size = format_size(tree.node(id).size(), options.human);
+            println!("{size}\t{}", tree.path_of(id).display());
+        }
+    }
+    if options.extensions {
+        for (extension, size, count) in query::by_extension(&tree) {
+            let size = format_size(size, options.human);
+            let extension = if extension.is_empty() {
+                "(none)".to_owned()
+            } else {
+                format!(".{extension}")
+            };
+            println!("{size}\t{count}\t{extension}");
         }
     }
-    println!("Total size of files at most 100000: {total_size}");
+    if options.json {
+        println!("{}", export::to_json(&tree, FsTree::ROOT));
+    }
+    if options.ncdu {
+        print!("{}", export::to_ncdu(&tree));
+    }
+    if options.materialize {
+        let root = disk::temp_root("day7")?;
+        disk::materialize(&tree, &root)?;
+        println!("Filesystem recreated in {}", root.display());
+    }
+    let reports = [
+        options.tree,
+        options.du,
+        options.top.is_some(),
+        options.find.is_some(),
+        options.sizes.is_some(),
+        options.extensions,
+        options.json,
+        options.ncdu,
+        options.materialize,
+    ];
+    if reports.contains(&true) {
+        return Ok(());
+    }
 
-    // Smallest directory to remove
-    let required_storage = 70000000 - 30000000;
-    let used_storage = compute_size(&tree, "/");
-    println!("Used storage: {used_storage}");
+    // Total size of the directories of at most the threshold
+    let total_size = small_directories_size(&tree, options.threshold);
+    println!("Part 1: {total_size}");
 
-    let needed_storage = used_storage - required_storage;
+    let used_storage = tree.size_of("/").unwrap();
+    println!("Used storage: {used_storage}");
+    let Some(needed_storage) = options.cleanup.needed(&tree) else {
+        println!("There is already enough free space");
+        return Ok(());
+    };
     println!("Needed storage: {needed_storage}");
 
-    let mut smallest_directory = PathBuf::default();
-    let mut smallest_size = u128::MAX;
-    for key in tree.keys() {
-        let size = compute_size(&tree, key);
-
-        if size >= needed_storage && size <= smallest_size {
-            smallest_size = size;
-            smallest_directory = key.to_owned();
+    // Smallest directory to remove
+    match options.cleanup.smallest_directory(&tree) {
+        Some(smallest_directory) => {
+            println!(
+                "Smallest directory at {:#?}",
+                tree.path_of(smallest_directory)
+            );
+            println!("Part 2: {}", tree.node(smallest_directory).size());
+        }
+        None => println!("Deleting everything wouldn't free enough space"),
+    }
+    if let Some(deletion) = options.cleanup.smallest_set(&tree) {
+        let total = deletion
+            .dirs
+            .iter()
+            .map(|id| tree.node(*id).size())
+            .sum::<u128>();
+        if deletion.exact {
+            println!("Smallest set of directories to remove, with size {total}:");
+        } else {
+            println!(
+                "Too large to look for the smallest set, removing the smallest directory instead, with size {total}:"
+            );
+        }
+        for id in deletion.dirs {
+            println!("{}\t{}", tree.node(id).size(), tree.path_of(id).display());
         }
     }
-    println!(
-        "Smallest directory at {:#?} with size {smallest_size}",
-        smallest_directory
-    );
 
     Ok(())
 }
@@ -158,9 +412,63 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 mod test {
     use std::path::PathBuf;
 
-    use super::fake_canonicalize;
+    use super::{
+        fake_canonicalize, read_input, small_directories_size, Cleanup, Command, Error, ListOutput,
+    };
+    use std::str::FromStr;
+
+    #[test]
+    fn list_output() {
+        let reason = |line| match ListOutput::from_str(line) {
+            Err(Error::ListOutputParseError(reason)) => reason,
+            result => panic!("Unexpected {result:?}"),
+        };
+        assert!(matches!(
+            ListOutput::from_str("dir  a "),
+            Ok(ListOutput::Directory(name)) if name.as_os_str() == "a"
+        ));
+        assert!(matches!(
+            ListOutput::from_str("12 dir"),
+            Ok(ListOutput::File(12, name)) if name.as_os_str() == "dir"
+        ));
+        assert_eq!(reason("dir"), "Missing name");
+        assert_eq!(reason("dirt x"), "Invalid file size");
+        assert_eq!(reason("dir .."), "Invalid name");
+        assert_eq!(reason("1 a/b"), "Invalid name");
+        assert_eq!(reason("18446744073709551616 a"), "Invalid file size");
+    }
+
+    #[test]
+    fn commands() {
+        assert!(matches!(Command::from_str("$ ls "), Ok(Command::List)));
+        assert!(matches!(
+            Command::from_str("$ cd"),
+            Ok(Command::CurrentDirectory(path)) if path.as_os_str() == "/"
+        ));
+        for line in ["$ ls -la", "$ ls x"] {
+            assert_eq!(
+                Command::from_str(line).unwrap_err().to_string(),
+                "ls takes no arguments"
+            );
+        }
+        assert!(matches!(
+            Command::from_str("$ mv a b"),
+            Err(Error::UnknownCommand(name)) if name == "mv"
+        ));
+    }
+
     #[test]
     fn canonicalize() {
         assert_eq!(fake_canonicalize("/a/e/../../d"), PathBuf::from("/d"));
+        assert_eq!(fake_canonicalize("/../a/./b/.."), PathBuf::from("/a"));
+    }
+
+    #[test]
+    fn sample() {
+        let tree = read_input(include_str!("sample_input.txt"), false).unwrap();
+        assert_eq!(tree.size_of("/"), Some(48381165));
+        assert_eq!(small_directories_size(&tree, 100000), 95437);
+        let smallest = Cleanup::default().smallest_directory(&tree).unwrap();
+        assert_eq!(tree.path_of(smallest), PathBuf::from("/d"));
     }
 }
@@ -0,0 +1,141 @@
+use std::{collections::HashMap, ops::RangeInclusive, path::Path};
+
+use crate::tree::{FsTree, NodeId};
+
+/// Matches a single name against a pattern where `*` stands for any number of
+/// characters and `?` for exactly one
+fn match_name(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_name(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_name(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_name(rest, &name[1..]),
+    }
+}
+
+/// Matches the names of a path against the parts of a pattern, `**` stands
+/// for any number of directories
+fn match_path(pattern: &[Vec<char>], path: &[Vec<char>]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((part, rest)) if part.iter().collect::<String>() == "**" => {
+            (0..=path.len()).any(|skip| match_path(rest, &path[skip..]))
+        }
+        Some((part, rest)) => {
+            !path.is_empty() && match_name(part, &path[0]) && match_path(rest, &path[1..])
+        }
+    }
+}
+
+/// Whether an absolute path matches a glob like `**/*.dat` or `/a/?/*`,
+/// patterns are relative to the root with or without a leading `/`
+pub fn glob_match(pattern: &str, path: impl AsRef<Path>) -> bool {
+    let pattern = pattern
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let path = path
+        .as_ref()
+        .iter()
+        .skip(1)
+        .map(|name| name.to_string_lossy().chars().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    match_path(&pattern, &path)
+}
+
+/// Every file and directory whose path matches the glob, depth first
+pub fn find(tree: &FsTree, pattern: &str) -> Vec<NodeId> {
+    tree.walk(FsTree::ROOT)
+        .map(|(id, _)| id)
+        .filter(|id| *id != FsTree::ROOT && glob_match(pattern, tree.path_of(*id)))
+        .collect()
+}
+
+/// The directories whose cumulative size is within `sizes`, depth first
+pub fn dirs_in_range(tree: &FsTree, sizes: &RangeInclusive<u128>) -> Vec<NodeId> {
+    tree.dirs()
+        .filter(|id| sizes.contains(&tree.node(*id).size()))
+        .collect()
+}
+
+/// Parses `MIN..MAX`, `MIN..` or `..MAX` into an inclusive range
+pub fn parse_size_range(s: &str) -> Option<RangeInclusive<u128>> {
+    let (min, max) = s.split_once("..")?;
+    let min = if min.is_empty() { 0 } else { min.parse().ok()? };
+    let max = if max.is_empty() {
+        u128::MAX
+    } else {
+        max.parse().ok()?
+    };
+    Some(min..=max)
+}
+
+/// The total size and number of files for each extension, the largest total
+/// first. Files without an extension are counted under an empty one.
+pub fn by_extension(tree: &FsTree) -> Vec<(String, u128, usize)> {
+    let mut totals = HashMap::<String, (u128, usize)>::default();
+    for (id, _) in tree.walk(FsTree::ROOT) {
+        let node = tree.node(id);
+        if node.is_dir() {
+            continue;
+        }
+        let extension = Path::new(node.name())
+            .extension()
+            .map_or(String::default(), |extension| {
+                extension.to_string_lossy().into_owned()
+            });
+        let total = totals.entry(extension).or_default();
+        total.0 += node.size();
+        total.1 += 1;
+    }
+    let mut totals = totals
+        .into_iter()
+        .map(|(extension, (size, count))| (extension, size, count))
+        .collect::<Vec<_>>();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{by_extension, dirs_in_range, find, glob_match, parse_size_range};
+    use crate::{read_input, tree::NodeId};
+    use std::path::PathBuf;
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("**/*.dat", "/c.dat"));
+        assert!(glob_match("**/*.dat", "/a/b/c.dat"));
+        assert!(!glob_match("**/*.dat", "/a/b/c.data"));
+        assert!(glob_match("/a/?/*", "/a/b/c.dat"));
+        assert!(!glob_match("a/?/*", "/a/bb/c"));
+        assert!(glob_match("a/**", "/a"));
+        assert!(glob_match("a/**/e", "/a/b/c/e"));
+        assert!(!glob_match("*", "/a/b"));
+    }
+
+    #[test]
+    fn queries() {
+        let tree = read_input(include_str!("sample_input.txt"), false).unwrap();
+        let paths = |ids: Vec<NodeId>| {
+            ids.into_iter()
+                .map(|id| tree.path_of(id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(find(&tree, "**/d.*")),
+            [PathBuf::from("/d/d.log"), PathBuf::from("/d/d.ext")]
+        );
+        let range = parse_size_range("..100000").unwrap();
+        assert_eq!(
+            paths(dirs_in_range(&tree, &range)),
+            [PathBuf::from("/a"), PathBuf::from("/a/e")]
+        );
+        assert_eq!(parse_size_range("5.."), Some(5..=u128::MAX));
+        assert_eq!(parse_size_range("5"), None);
+        let extensions = by_extension(&tree);
+        assert_eq!(extensions[0], ("txt".to_owned(), 14848514, 1));
+        assert_eq!(extensions[1], ("".to_owned(), 11306727, 5));
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use crate::tree::{FsTree, NodeId};
+
+/// Formats a size like `du -h`, with one decimal below 10 of a unit, always
+/// rounding up
+pub fn human_size(size: u128) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut divisor = 1;
+    for (index, unit) in UNITS.iter().enumerate() {
+        divisor *= 1024;
+        if size < 10 * divisor {
+            let tenths = (size * 10).div_ceil(divisor);
+            if tenths < 100 {
+                return format!("{}.{}{unit}", tenths / 10, tenths % 10);
+            }
+        }
+        let whole = size.div_ceil(divisor);
+        // Rounding up can reach the next unit, shown as 1.0 of it
+        if whole < 1024 || index == UNITS.len() - 1 {
+            return format!("{whole}{unit}");
+        }
+    }
+    unreachable!()
+}
+
+/// The size as is or like `du -h`
+pub fn format_size(size: u128, human: bool) -> String {
+    if human {
+        human_size(size)
+    } else {
+        size.to_string()
+    }
+}
+
+/// Renders the filesystem below `id` like `tree`, entries sorted by name and
+/// followed by their size. Only the entries up to `max_depth` levels below
+/// `id` are shown if given.
+pub fn tree_view(tree: &FsTree, id: NodeId, human: bool, max_depth: Option<usize>) -> String {
+    fn render(
+        tree: &FsTree,
+        id: NodeId,
+        prefix: &str,
+        depth: usize,
+        options: (bool, Option<usize>),
+        output: &mut String,
+    ) {
+        let (human, max_depth) = options;
+        if max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            return;
+        }
+        let mut children = tree.children(id).to_vec();
+        children.sort_by(|a, b| tree.node(*a).name().cmp(tree.node(*b).name()));
+        for (index, child) in children.iter().enumerate() {
+            let last = index + 1 == children.len();
+            let node = tree.node(*child);
+            let _ = writeln!(
+                output,
+                "{prefix}{} {} ({})",
+                if last { "└──" } else { "├──" },
+                node.name(),
+                format_size(node.size(), human)
+            );
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            render(tree, *child, &prefix, depth + 1, options, output);
+        }
+    }
+
+    let node = tree.node(id);
+    let mut output = format!("{} ({})\n", node.name(), format_size(node.size(), human));
+    render(tree, id, "", 0, (human, max_depth), &mut output);
+    output
+}
+
+/// Lists every directory below `id`, itself included, with its size like
+/// `du`, the largest first
+pub fn disk_usage(tree: &FsTree, id: NodeId, human: bool) -> String {
+    let mut dirs = tree
+        .walk(id)
+        .map(|(id, _)| id)
+        .filter(|id| tree.node(*id).is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort_by_key(|id| std::cmp::Reverse(tree.node(*id).size()));
+    dirs.into_iter()
+        .map(|id| {
+            format!(
+                "{}\t{}\n",
+                format_size(tree.node(id).size(), human),
+                tree.path_of(id).display()
+            )
+        })
+        .collect()
+}
+
+/// The `count` largest directories or files, the largest first
+pub fn largest(tree: &FsTree, count: usize, dirs: bool) -> Vec<NodeId> {
+    let mut nodes = tree
+        .walk(FsTree::ROOT)
+        .map(|(id, _)| id)
+        .filter(|id| tree.node(*id).is_dir() == dirs)
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|id| std::cmp::Reverse(tree.node(*id).size()));
+    nodes.truncate(count);
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disk_usage, human_size, largest, tree_view};
+    use crate::{read_input, tree::FsTree};
+
+    #[test]
+    fn sizes() {
+        assert_eq!(human_size(584), "584");
+        assert_eq!(human_size(94853), "93K");
+        // Rounded up like `du -h`, 8.11M and 46.14M
+        assert_eq!(human_size(8504156), "8.2M");
+        assert_eq!(human_size(48381165), "47M");
+        assert_eq!(human_size(10239), "10K");
+        assert_eq!(human_size(1047552), "1023K");
+        assert_eq!(human_size(1048575), "1.0M");
+        assert_eq!(human_size(1 << 20), "1.0M");
+        assert_eq!(human_size(u128::MAX), "295147905179352825856E");
+    }
+
+    #[test]
+    fn reports() {
+        let tree = read_input(include_str!("sample_input.txt"), false).unwrap();
+        assert_eq!(
+            tree_view(&tree, FsTree::ROOT, true, Some(1)),
+            "/ (47M)\n├── a (93K)\n├── b.txt (15M)\n├── c.dat (8.2M)\n└── d (24M)\n"
+        );
+        let view = tree_view(&tree, FsTree::ROOT, false, None);
+        assert!(
+            view.starts_with("/ (48381165)\n├── a (94853)\n│   ├── e (584)\n│   │   └── i (584)\n")
+        );
+        assert!(view.ends_with("└── d (24933642)\n    ├── d.ext (5626152)\n    ├── d.log (8033020)\n    ├── j (4060174)\n    └── k (7214296)\n"));
+        assert_eq!(
+            disk_usage(&tree, FsTree::ROOT, true),
+            "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n"
+        );
+        let files = largest(&tree, 2, false)
+            .into_iter()
+            .map(|id| tree.node(id).name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(files, ["b.txt", "c.dat"]);
+    }
+}
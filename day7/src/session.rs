@@ -0,0 +1,285 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    fake_canonicalize,
+    tree::{FsTree, NodeId},
+    Command, Error, ListOutput,
+};
+
+/// Something in a transcript that contradicts what was seen before. The
+/// first version of an entry is the one kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    FileAndDirectory(PathBuf),
+    SizeMismatch {
+        path: PathBuf,
+        size: u128,
+        previous: u128,
+    },
+    /// The same name twice in one listing
+    DuplicateEntry(PathBuf),
+    /// An entry missing when listing a directory again
+    MissingEntry(PathBuf),
+    /// `cd` to a directory no listing showed
+    UnlistedDirectory(PathBuf),
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FileAndDirectory(path) => {
+                write!(f, "Both a file and a directory: {}", path.display())
+            }
+            Self::SizeMismatch {
+                path,
+                size,
+                previous,
+            } => write!(
+                f,
+                "{} has size {size} but was listed with size {previous}",
+                path.display()
+            ),
+            Self::DuplicateEntry(path) => write!(f, "Listed twice: {}", path.display()),
+            Self::MissingEntry(path) => write!(f, "No longer listed: {}", path.display()),
+            Self::UnlistedDirectory(path) => {
+                write!(f, "Directory never listed: {}", path.display())
+            }
+        }
+    }
+}
+
+/// Replays a terminal session to rebuild the filesystem it explored
+#[derive(Debug, Default)]
+pub struct Session {
+    tree: FsTree,
+    current_directory: NodeId,
+    /// The directory whose `ls` output is being read
+    listing: Option<NodeId>,
+    /// The line being executed
+    line: usize,
+    /// The line of the running `ls` and the names it output so far
+    listing_line: usize,
+    listed_names: HashSet<String>,
+    /// The directories whose listing is complete
+    listed: HashSet<NodeId>,
+    issues: Vec<(usize, Issue)>,
+}
+
+impl Session {
+    /// Replays the whole transcript and lists its inconsistencies with the
+    /// line they were found at. Errors hold the line they happened at.
+    pub fn validate(transcript: &str) -> Result<(FsTree, Vec<(usize, Issue)>), Error> {
+        let mut session = Self::default();
+        for (index, line) in transcript.lines().enumerate() {
+            session.line = index + 1;
+            session.execute(line).map_err(|error| Error::Line {
+                line: index + 1,
+                error: Box::new(error),
+            })?;
+        }
+        session.finish_listing();
+        session.issues.sort_by_key(|(line, _)| *line);
+        let mut tree = session.tree;
+        tree.compute_sizes();
+        Ok((tree, session.issues))
+    }
+
+    fn report(&mut self, line: usize, issue: Issue) {
+        self.issues.push((line, issue));
+    }
+
+    /// Checks that listing a directory again didn't lose any entry
+    fn finish_listing(&mut self) {
+        let Some(directory) = self.listing.take() else {
+            return;
+        };
+        if !self.listed.insert(directory) {
+            let missing = self
+                .tree
+                .children(directory)
+                .iter()
+                .filter(|child| !self.listed_names.contains(self.tree.node(**child).name()))
+                .map(|child| self.tree.path_of(*child))
+                .collect::<Vec<_>>();
+            for path in missing {
+                self.report(self.listing_line, Issue::MissingEntry(path));
+            }
+        }
+        self.listed_names.clear();
+    }
+
+    /// Handles a single line of the transcript, either a command or the
+    /// output of the last `ls`
+    pub fn execute(&mut self, line: &str) -> Result<(), Error> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        if line.starts_with('$') {
+            self.finish_listing();
+            match Command::from_str(line)? {
+                Command::CurrentDirectory(path) => self.change_directory(path)?,
+                Command::List => {
+                    self.listing = Some(self.current_directory);
+                    self.listing_line = self.line;
+                }
+            }
+            return Ok(());
+        }
+        let directory = self
+            .listing
+            .ok_or_else(|| Error::UnexpectedOutput(line.to_owned()))?;
+        let output = ListOutput::from_str(line)?;
+        let (name, size) = match &output {
+            ListOutput::Directory(name) => (name.to_string_lossy(), None),
+            ListOutput::File(size, name) => (name.to_string_lossy(), Some(*size)),
+        };
+        let path = self.tree.path_of(directory).join(name.as_ref());
+        if !self.listed_names.insert(name.to_string()) {
+            self.report(self.line, Issue::DuplicateEntry(path.clone()));
+        }
+        // Listing the same directory again doesn't add the entries twice
+        match (self.tree.child(directory, &name), size) {
+            (None, None) => {
+                self.tree.add_dir(directory, &name);
+            }
+            (None, Some(size)) => {
+                self.tree.add_file(directory, &name, size);
+            }
+            (Some(existing), size) => {
+                let node = self.tree.node(existing);
+                match size {
+                    _ if node.is_dir() != size.is_none() => {
+                        self.report(self.line, Issue::FileAndDirectory(path))
+                    }
+                    Some(size) if size != node.size() => {
+                        let previous = node.size();
+                        self.report(
+                            self.line,
+                            Issue::SizeMismatch {
+                                path,
+                                size,
+                                previous,
+                            },
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves to a relative or absolute path, creating the directories that
+    /// were not listed yet. `..` at the root stays at the root.
+    pub fn change_directory(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = fake_canonicalize(self.tree.path_of(self.current_directory).join(path));
+        let mut directory = FsTree::ROOT;
+        let mut unlisted = false;
+        for component in path.components() {
+            if let Component::Normal(name) = component {
+                let name = name.to_string_lossy();
+                directory = match self.tree.child(directory, &name) {
+                    Some(child) if !self.tree.node(child).is_dir() => {
+                        return Err(Error::NotADirectory(self.tree.path_of(child)))
+                    }
+                    Some(child) => child,
+                    None => {
+                        unlisted = true;
+                        self.tree.add_dir(directory, &name)
+                    }
+                };
+            }
+        }
+        if unlisted {
+            self.report(self.line, Issue::UnlistedDirectory(path));
+        }
+        self.current_directory = directory;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Issue, Session};
+    use crate::{tree::FsTree, Error};
+    use aoc::fuzz;
+    use std::path::PathBuf;
+
+    #[test]
+    fn navigation() {
+        let transcript = "$ cd ..\n$ cd .\n$ cd /a/b\n$ ls\n1 f\n$ cd ../../c/./d\n$ ls\n2 g\n$ cd /\n$ ls\ndir a\n$ cd a/b\n$ ls\n1 f\n";
+        let (tree, _) = Session::validate(transcript).unwrap();
+        assert_eq!(tree.size_of("/a/b"), Some(1));
+        assert_eq!(tree.size_of("/c/d"), Some(2));
+        assert_eq!(tree.size_of("/"), Some(3));
+    }
+
+    #[test]
+    fn errors() {
+        let line = |transcript| match Session::validate(transcript) {
+            Err(Error::Line { line, error }) => (line, error.to_string()),
+            result => panic!("Unexpected {result:?}"),
+        };
+        assert_eq!(
+            line("$ cd /\n$ ls\n$ rm -rf a\n"),
+            (3, "Unknown command rm".to_owned())
+        );
+        assert_eq!(
+            line("$ cd /\n1 a\n"),
+            (2, "Output without ls: 1 a".to_owned())
+        );
+        assert_eq!(
+            line("$ ls\n1 a\n$ cd a\n"),
+            (3, "Not a directory: /a".to_owned())
+        );
+    }
+
+    #[test]
+    fn inconsistencies() {
+        let transcript = "$ cd /\n$ ls\ndir a\n10 b\n10 b\n$ cd a\n$ ls\n1 c\n$ cd /\n$ ls\n20 b\n$ ls\n4 a\n$ cd d\n";
+        let (tree, issues) = Session::validate(transcript).unwrap();
+        assert_eq!(
+            issues,
+            [
+                (5, Issue::DuplicateEntry(PathBuf::from("/b"))),
+                (10, Issue::MissingEntry(PathBuf::from("/a"))),
+                (
+                    11,
+                    Issue::SizeMismatch {
+                        path: PathBuf::from("/b"),
+                        size: 20,
+                        previous: 10
+                    }
+                ),
+                (12, Issue::MissingEntry(PathBuf::from("/b"))),
+                (13, Issue::FileAndDirectory(PathBuf::from("/a"))),
+                (14, Issue::UnlistedDirectory(PathBuf::from("/d"))),
+            ]
+        );
+        // The first version of each entry is kept
+        assert_eq!(tree.size_of("/"), Some(11));
+        assert!(Session::validate(include_str!("input.txt"))
+            .unwrap()
+            .1
+            .is_empty());
+    }
+
+    #[test]
+    fn fuzz_validate() {
+        fuzz::check(&[include_str!("sample_input.txt")], |transcript| {
+            let Ok((tree, _)) = Session::validate(transcript) else {
+                return;
+            };
+            // Every node can be found again from its path, so no name is empty or
+            // moves to another directory
+            for (id, _) in tree.walk(FsTree::ROOT) {
+                assert_eq!(tree.lookup(tree.path_of(id)), Some(id));
+            }
+        });
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    cleanup::Cleanup,
+    fake_canonicalize, query,
+    report::{disk_usage, format_size},
+    tree::{FsTree, NodeId},
+    Command, Error,
+};
+
+/// A line typed in the [`Shell`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShellCommand {
+    /// `cd` or `ls` as in the transcripts
+    Transcript(Command),
+    /// `ls` of a given directory, or of a file
+    ListPath(PathBuf),
+    /// `du`, with `-h` for human readable sizes
+    DiskUsage {
+        human: bool,
+    },
+    Find(String),
+    Remove(String),
+    /// `df`
+    FreeSpace,
+    /// `exit` or `quit`
+    Exit,
+}
+
+impl FromStr for ShellCommand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s.trim_start_matches('$').trim();
+        let (name, argument) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(name, argument)| (name, argument.trim()));
+        match (name, argument) {
+            ("ls", path) if !path.is_empty() => Ok(Self::ListPath(path.into())),
+            ("cd" | "ls", _) => Command::from_str(line).map(Self::Transcript),
+            ("du", "") => Ok(Self::DiskUsage { human: false }),
+            ("du", "-h") => Ok(Self::DiskUsage { human: true }),
+            ("find", "") => Err(Error::CommandParseError("find expects a glob")),
+            ("find", pattern) => Ok(Self::Find(pattern.to_owned())),
+            ("rm", "") => Err(Error::CommandParseError("rm expects a path")),
+            ("rm", path) => Ok(Self::Remove(path.to_owned())),
+            ("df", "") => Ok(Self::FreeSpace),
+            ("exit" | "quit", "") => Ok(Self::Exit),
+            _ if line.is_empty() => Err(Error::CommandParseError("Missing command")),
+            _ => Err(Error::UnknownCommand(name.to_owned())),
+        }
+    }
+}
+
+/// An interactive shell over a parsed filesystem. It understands the same
+/// `cd` and `ls` as the transcripts, plus `du`, `find`, `rm` and `exit`.
+/// Deleting only happens in memory.
+pub struct Shell {
+    tree: FsTree,
+    current_directory: NodeId,
+    cleanup: Cleanup,
+    /// The space freed by `rm` so far
+    freed: u128,
+    pub exited: bool,
+}
+
+impl Shell {
+    pub fn new(tree: FsTree, cleanup: Cleanup) -> Self {
+        Self {
+            tree,
+            current_directory: FsTree::ROOT,
+            cleanup,
+            freed: 0,
+            exited: false,
+        }
+    }
+
+    /// The prompt to show before reading a line
+    pub fn prompt(&self) -> String {
+        format!("{}$ ", self.current_directory().display())
+    }
+
+    /// Resolves a path relative to the current directory
+    fn resolve(&self, path: impl AsRef<Path>) -> Result<NodeId, Error> {
+        let path = fake_canonicalize(self.current_directory().join(path));
+        self.tree.lookup(&path).ok_or(Error::NotFound(path))
+    }
+
+    /// Runs one line and returns what it printed
+    pub fn execute(&mut self, line: &str) -> Result<String, Error> {
+        if line.trim().trim_start_matches('$').trim().is_empty() {
+            return Ok(String::default());
+        }
+        match ShellCommand::from_str(line)? {
+            ShellCommand::Transcript(Command::CurrentDirectory(path)) => {
+                let id = self.resolve(path)?;
+                if !self.tree.node(id).is_dir() {
+                    return Err(Error::NotADirectory(self.tree.path_of(id)));
+                }
+                self.current_directory = id;
+                Ok(String::default())
+            }
+            ShellCommand::Transcript(Command::List) => Ok(self.list(self.current_directory)),
+            ShellCommand::ListPath(path) => {
+                let id = self.resolve(path)?;
+                Ok(self.list(id))
+            }
+            ShellCommand::DiskUsage { human } => {
+                Ok(disk_usage(&self.tree, self.current_directory, human))
+            }
+            ShellCommand::Find(pattern) => {
+                let pattern = fake_canonicalize(self.current_directory().join(pattern));
+                let mut output = String::default();
+                for id in query::find(&self.tree, &pattern.to_string_lossy()) {
+                    let _ = writeln!(output, "{}", self.tree.path_of(id).display());
+                }
+                Ok(output)
+            }
+            ShellCommand::Remove(path) => self.remove(&path),
+            ShellCommand::FreeSpace => Ok(self.free_space()),
+            ShellCommand::Exit => {
+                self.exited = true;
+                Ok(String::default())
+            }
+        }
+    }
+
+    /// The entries of a directory like in a transcript, or the file itself
+    fn list(&self, id: NodeId) -> String {
+        let node = self.tree.node(id);
+        if !node.is_dir() {
+            return format!("{} {}\n", node.size(), node.name());
+        }
+        let mut children = self.tree.children(id).to_vec();
+        children.sort_by(|a, b| self.tree.node(*a).name().cmp(self.tree.node(*b).name()));
+        children
+            .into_iter()
+            .map(|child| {
+                let child = self.tree.node(child);
+                if child.is_dir() {
+                    format!("dir {}\n", child.name())
+                } else {
+                    format!("{} {}\n", child.size(), child.name())
+                }
+            })
+            .collect()
+    }
+
+    fn remove(&mut self, path: &str) -> Result<String, Error> {
+        let id = self.resolve(path)?;
+        if id == FsTree::ROOT {
+            return Err(Error::CannotRemoveRoot);
+        }
+        let path = self.tree.path_of(id);
+        let size = self.tree.node(id).size();
+        // Leave a removed directory
+        if self.current_directory().starts_with(&path) {
+            self.current_directory = self.tree.lookup(path.parent().unwrap()).unwrap();
+        }
+        self.tree.remove(id);
+        self.freed += size;
+        Ok(format!(
+            "Removed {} ({})\n{}",
+            path.display(),
+            format_size(size, true),
+            self.free_space()
+        ))
+    }
+
+    /// The used space, what was freed and what still needs to be
+    fn free_space(&self) -> String {
+        let used = self.tree.node(FsTree::ROOT).size();
+        let mut output = format!("Used storage: {used}\nFreed storage: {}\n", self.freed);
+        match self.cleanup.needed(&self.tree) {
+            Some(needed) => {
+                let _ = writeln!(output, "Needed storage: {needed}");
+            }
+            None => output.push_str("There is enough free space\n"),
+        }
+        output
+    }
+
+    pub fn current_directory(&self) -> PathBuf {
+        self.tree.path_of(self.current_directory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shell, ShellCommand};
+    use crate::{cleanup::Cleanup, read_input, Command, Error};
+    use std::{path::PathBuf, str::FromStr};
+
+    #[test]
+    fn commands() {
+        let parse = |line| ShellCommand::from_str(line).unwrap();
+        assert_eq!(parse("$ ls"), ShellCommand::Transcript(Command::List));
+        assert_eq!(parse("ls ../f"), ShellCommand::ListPath("../f".into()));
+        assert_eq!(
+            parse("cd a"),
+            ShellCommand::Transcript(Command::CurrentDirectory("a".into()))
+        );
+        assert_eq!(parse("du -h"), ShellCommand::DiskUsage { human: true });
+        assert_eq!(parse("rm  a b "), ShellCommand::Remove("a b".to_owned()));
+        assert_eq!(parse("quit"), ShellCommand::Exit);
+        assert!(matches!(
+            ShellCommand::from_str("du x"),
+            Err(Error::UnknownCommand(name)) if name == "du"
+        ));
+        assert!(matches!(
+            ShellCommand::from_str("find"),
+            Err(Error::CommandParseError(_))
+        ));
+    }
+
+    #[test]
+    fn explore() {
+        let tree = read_input(include_str!("sample_input.txt"), false).unwrap();
+        let mut shell = Shell::new(tree, Cleanup::default());
+        assert_eq!(
+            shell.execute("$ ls").unwrap(),
+            "dir a\n14848514 b.txt\n8504156 c.dat\ndir d\n"
+        );
+        shell.execute("cd a/e").unwrap();
+        assert_eq!(shell.prompt(), "/a/e$ ");
+        assert_eq!(shell.execute("ls ../f").unwrap(), "29116 f\n");
+        assert_eq!(shell.execute("du").unwrap(), "584\t/a/e\n");
+        assert_eq!(
+            shell.execute("find ../*").unwrap(),
+            "/a/e\n/a/f\n/a/g\n/a/h.lst\n"
+        );
+        assert_eq!(shell.execute("find /*.dat").unwrap(), "/c.dat\n");
+        shell.execute("cd").unwrap();
+        assert_eq!(
+            shell.execute("find **/?").unwrap(),
+            "/a\n/a/e\n/a/e/i\n/a/f\n/a/g\n/d\n/d/j\n/d/k\n"
+        );
+        assert!(matches!(
+            shell.execute("cd c.dat"),
+            Err(Error::NotADirectory(_))
+        ));
+        assert!(matches!(shell.execute("cd x"), Err(Error::NotFound(_))));
+        assert!(matches!(
+            shell.execute("mv a b"),
+            Err(Error::UnknownCommand(_))
+        ));
+        shell.execute("exit").unwrap();
+        assert!(shell.exited);
+    }
+
+    #[test]
+    fn remove() {
+        let tree = read_input(include_str!("sample_input.txt"), false).unwrap();
+        let mut shell = Shell::new(tree, Cleanup::default());
+        assert!(shell
+            .execute("df")
+            .unwrap()
+            .ends_with("Needed storage: 8381165\n"));
+        shell.execute("cd /a/e").unwrap();
+        assert_eq!(
+            shell.execute("rm /a").unwrap(),
+            "Removed /a (93K)\nUsed storage: 48286312\nFreed storage: 94853\nNeeded storage: 8286312\n"
+        );
+        assert_eq!(shell.current_directory(), PathBuf::from("/"));
+        assert!(shell
+            .execute("rm d")
+            .unwrap()
+            .ends_with("There is enough free space\n"));
+        assert_eq!(
+            shell.execute("ls").unwrap(),
+            "14848514 b.txt\n8504156 c.dat\n"
+        );
+        assert!(matches!(
+            shell.execute("rm /"),
+            Err(Error::CannotRemoveRoot)
+        ));
+        assert!(matches!(
+            shell.execute("rm a/.."),
+            Err(Error::CannotRemoveRoot)
+        ));
+    }
+}
@@ -0,0 +1,268 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Index of a node in its [`FsTree`]
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    Dir(Vec<NodeId>),
+    File(u128),
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    name: String,
+    parent: Option<NodeId>,
+    kind: NodeKind,
+    /// The size of a file, or the cumulative size of a directory
+    size: u128,
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Dir(_))
+    }
+
+    /// The size of a file, or the size of everything inside a directory as
+    /// of the last [`FsTree::compute_sizes`]
+    pub fn size(&self) -> u128 {
+        self.size
+    }
+}
+
+/// The filesystem reconstructed from a terminal session.
+///
+/// Nodes are stored in an arena, a node always comes after its parent.
+#[derive(Debug, Clone)]
+pub struct FsTree {
+    nodes: Vec<Node>,
+}
+
+impl Default for FsTree {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: "/".to_owned(),
+                parent: None,
+                kind: NodeKind::Dir(Vec::default()),
+                size: 0,
+            }],
+        }
+    }
+}
+
+impl FsTree {
+    pub const ROOT: NodeId = 0;
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    /// The entries of a directory, nothing for a file
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        match &self.nodes[id].kind {
+            NodeKind::Dir(children) => children,
+            NodeKind::File(_) => &[],
+        }
+    }
+
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.children(dir)
+            .iter()
+            .copied()
+            .find(|child| self.nodes[*child].name == name)
+    }
+
+    fn add(&mut self, parent: NodeId, name: &str, kind: NodeKind) -> NodeId {
+        if let Some(existing) = self.child(parent, name) {
+            return existing;
+        }
+        let id = self.nodes.len();
+        let size = match kind {
+            NodeKind::File(size) => size,
+            NodeKind::Dir(_) => 0,
+        };
+        self.nodes.push(Node {
+            name: name.to_owned(),
+            parent: Some(parent),
+            kind,
+            size,
+        });
+        match &mut self.nodes[parent].kind {
+            NodeKind::Dir(children) => children.push(id),
+            NodeKind::File(_) => panic!("{} is not a directory", self.nodes[parent].name),
+        }
+        id
+    }
+
+    /// Adds a directory to `parent`, or returns the existing entry with that name
+    pub fn add_dir(&mut self, parent: NodeId, name: &str) -> NodeId {
+        self.add(parent, name, NodeKind::Dir(Vec::default()))
+    }
+
+    /// Adds a file to `parent`, or returns the existing entry with that name
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: u128) -> NodeId {
+        self.add(parent, name, NodeKind::File(size))
+    }
+
+    /// Finds a node from its absolute path
+    pub fn lookup(&self, path: impl AsRef<Path>) -> Option<NodeId> {
+        let mut current = Self::ROOT;
+        for component in path.as_ref().components() {
+            match component {
+                Component::RootDir => current = Self::ROOT,
+                Component::Normal(name) => current = self.child(current, name.to_str()?)?,
+                _ => return None,
+            }
+        }
+        Some(current)
+    }
+
+    pub fn size_of(&self, path: impl AsRef<Path>) -> Option<u128> {
+        self.lookup(path).map(|id| self.nodes[id].size)
+    }
+
+    pub fn path_of(&self, id: NodeId) -> PathBuf {
+        let mut names = Vec::default();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            names.push(self.nodes[id].name.as_str());
+            current = self.nodes[id].parent;
+        }
+        names.into_iter().rev().collect()
+    }
+
+    /// Detaches a node and everything inside it from the tree, the sizes of
+    /// the directories containing it are kept up to date
+    pub fn remove(&mut self, id: NodeId) {
+        let Some(parent) = self.nodes[id].parent.take() else {
+            return;
+        };
+        if let NodeKind::Dir(children) = &mut self.nodes[parent].kind {
+            children.retain(|child| *child != id);
+        }
+        let size = self.nodes[id].size;
+        let mut current = Some(parent);
+        while let Some(ancestor) = current {
+            self.nodes[ancestor].size -= size;
+            current = self.nodes[ancestor].parent;
+        }
+    }
+
+    /// Iterates over every node below `id`, itself included, depth first with
+    /// how deep each node is relative to `id`
+    pub fn walk(&self, id: NodeId) -> impl Iterator<Item = (NodeId, usize)> + '_ {
+        let mut pending = vec![(id, 0)];
+        std::iter::from_fn(move || {
+            let (id, depth) = pending.pop()?;
+            pending.extend(
+                self.children(id)
+                    .iter()
+                    .rev()
+                    .map(|child| (*child, depth + 1)),
+            );
+            Some((id, depth))
+        })
+    }
+
+    /// Every directory, the root included
+    pub fn dirs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.walk(Self::ROOT)
+            .map(|(id, _)| id)
+            .filter(|id| self.nodes[*id].is_dir())
+    }
+
+    /// Computes the cumulative size of every directory at once, going
+    /// bottom-up as children always come after their parent
+    pub fn compute_sizes(&mut self) {
+        for node in self.nodes.iter_mut() {
+            if node.is_dir() {
+                node.size = 0;
+            }
+        }
+        for id in (1..self.nodes.len()).rev() {
+            // Removed nodes have no parent
+            if let Some(parent) = self.nodes[id].parent {
+                self.nodes[parent].size += self.nodes[id].size;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FsTree, NodeId};
+    use crate::read_input;
+    use aoc::{gen, rng::Rng};
+    use std::path::PathBuf;
+
+    /// The size of a node added up from its files, ignoring the cached sizes
+    fn naive_size(tree: &FsTree, id: NodeId) -> u128 {
+        if tree.node(id).is_dir() {
+            tree.children(id)
+                .iter()
+                .map(|child| naive_size(tree, *child))
+                .sum()
+        } else {
+            tree.node(id).size()
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        let mut tree = FsTree::default();
+        let a = tree.add_dir(FsTree::ROOT, "a");
+        let e = tree.add_dir(a, "e");
+        tree.add_file(e, "i", 584);
+        tree.add_file(a, "f", 29116);
+        tree.add_file(FsTree::ROOT, "b.txt", 14848514);
+        tree.compute_sizes();
+        assert_eq!(tree.size_of("/a/e"), Some(584));
+        assert_eq!(tree.size_of("/a"), Some(29700));
+        assert_eq!(tree.size_of("/"), Some(14878214));
+        assert_eq!(tree.size_of("/c"), None);
+        assert_eq!(
+            tree.path_of(tree.lookup("/a/e/i").unwrap()),
+            PathBuf::from("/a/e/i")
+        );
+        let names = tree
+            .walk(FsTree::ROOT)
+            .map(|(id, depth)| format!("{}{}", " ".repeat(depth), tree.node(id).name()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["/", " a", "  e", "   i", "  f", " b.txt"]);
+        tree.remove(e);
+        assert_eq!(tree.size_of("/a"), Some(29116));
+        assert_eq!(tree.size_of("/"), Some(14877630));
+        assert_eq!(tree.lookup("/a/e"), None);
+        tree.compute_sizes();
+        assert_eq!(tree.size_of("/"), Some(14877630));
+    }
+
+    #[test]
+    fn cached_sizes_match_naive() {
+        let mut rng = Rng::new(7);
+        for seed in 0..50 {
+            let session = gen::terminal_session(&mut Rng::new(seed), 100);
+            let mut tree = read_input(&session, true).unwrap();
+            for _ in 0..5 {
+                for (id, _) in tree.walk(FsTree::ROOT) {
+                    assert_eq!(tree.node(id).size(), naive_size(&tree, id));
+                }
+                let nodes = tree.walk(FsTree::ROOT).skip(1).collect::<Vec<_>>();
+                if nodes.is_empty() {
+                    break;
+                }
+                tree.remove(rng.pick(&nodes).0);
+            }
+            let mut recomputed = tree.clone();
+            recomputed.compute_sizes();
+            for (id, _) in tree.walk(FsTree::ROOT) {
+                assert_eq!(recomputed.node(id).size(), tree.node(id).size());
+            }
+        }
+    }
+}